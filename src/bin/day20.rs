@@ -0,0 +1,3 @@
+fn main() -> anyhow::Result<()> {
+    advent::solve_with_static_input(20)
+}
@@ -0,0 +1,3 @@
+fn main() -> anyhow::Result<()> {
+    advent::solve_with_cached_input(22)
+}
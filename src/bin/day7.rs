@@ -1,3 +1,3 @@
-fn main() {
-    advent::solve_with_static_input(7);
+fn main() -> anyhow::Result<()> {
+    advent::solve_with_static_input(7)
 }
@@ -1,38 +1,197 @@
+use crate::params;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Besides the two answers, `-p day01.top=<n>` adds the total of the top `n`
+/// elves, and `-p day01.stats=true` adds a summary of the whole inventory.
 pub fn solve(input: &str) -> String {
-    // part 1
-    let calorie_stream: Vec<Option<u32>> = input.lines().map(|s| s.parse().ok()).collect();
-    let mut elves: Vec<Vec<u32>> = Vec::new();
-    let mut elf = Vec::new();
-    for c in calorie_stream {
-        match c {
-            Some(val) => elf.push(val),
-            None => {
-                // elf is done
-                elves.push(elf.clone());
-                elf.clear();
+    let inventory: Inventory = input.parse().expect("bad calorie list");
+    let p1 = inventory.top_total(1);
+    let p2 = inventory.top_total(3);
+    let mut out = format!("{p1}, {p2}");
+
+    if let Some(n) = params::get::<usize>("day01.top") {
+        out += &format!(", top {n}: {}", inventory.top_total(n));
+    }
+    if params::get::<bool>("day01.stats").unwrap_or(false) {
+        out += &format!(" ({})", inventory.summary());
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Elf {
+    items: Vec<u32>,
+}
+
+impl Elf {
+    fn total(&self) -> u32 {
+        self.items.iter().sum()
+    }
+}
+
+/// Every elf's snacks, in input order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Inventory {
+    elves: Vec<Elf>,
+}
+
+// groups of numbers separated by blank lines, with or without a blank line
+// at the end
+impl FromStr for Inventory {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut elves = Vec::new();
+        let mut items = Vec::new();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() {
+                if !items.is_empty() {
+                    elves.push(Elf {
+                        items: std::mem::take(&mut items),
+                    });
+                }
+            } else {
+                items.push(line.parse().map_err(|_| ())?);
+            }
+        }
+        if !items.is_empty() {
+            elves.push(Elf { items });
+        }
+        Ok(Inventory { elves })
+    }
+}
+
+impl Inventory {
+    /// The `n` biggest totals, biggest first.
+    ///
+    /// Keeps a min-heap of the best `n` seen so far, so this is O(elves log n).
+    fn top(&self, n: usize) -> Vec<u32> {
+        let mut heap = BinaryHeap::with_capacity(n + 1);
+        for elf in &self.elves {
+            heap.push(Reverse(elf.total()));
+            if heap.len() > n {
+                heap.pop();
             }
         }
+        // ascending Reverse is descending totals
+        heap.into_sorted_vec().into_iter().map(|Reverse(t)| t).collect()
+    }
+
+    fn top_total(&self, n: usize) -> u32 {
+        self.top(n).iter().sum()
+    }
+
+    fn summary(&self) -> Summary {
+        let mut totals: Vec<u32> = self.elves.iter().map(Elf::total).collect();
+        totals.sort_unstable();
+        Summary {
+            item_counts: self.elves.iter().map(|e| e.items.len()).collect(),
+            totals,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Summary {
+    // how many items each elf has, in input order
+    item_counts: Vec<usize>,
+    // sorted ascending
+    totals: Vec<u32>,
+}
+
+impl Summary {
+    fn items(&self) -> usize {
+        self.item_counts.iter().sum()
+    }
+
+    fn fewest_items(&self) -> Option<usize> {
+        self.item_counts.iter().copied().min()
+    }
+
+    fn most_items(&self) -> Option<usize> {
+        self.item_counts.iter().copied().max()
+    }
+
+    /// Nearest-rank percentile of the elf totals, `p` in `0.0..=100.0`.
+    fn percentile(&self, p: f64) -> Option<u32> {
+        if self.totals.is_empty() {
+            return None;
+        }
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * self.totals.len() as f64).ceil() as usize;
+        Some(self.totals[rank.max(1) - 1])
+    }
+
+    fn median(&self) -> Option<u32> {
+        self.percentile(50.0)
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elves = self.item_counts.len();
+        let items_per_elf = self.items() as f64 / elves.max(1) as f64;
+        write!(
+            f,
+            "{elves} elves, {} items ({} to {} per elf, {items_per_elf:.1} on average), median {}, p90 {}",
+            self.items(),
+            self.fewest_items().unwrap_or(0),
+            self.most_items().unwrap_or(0),
+            self.median().unwrap_or(0),
+            self.percentile(90.0).unwrap_or(0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        let inventory: Inventory = TEST_INPUT.parse().unwrap();
+        assert_eq!(vec![24000, 11000, 10000], inventory.top(3));
+        assert_eq!(45000, inventory.top_total(3));
+        assert_eq!(55000, inventory.top_total(10));
+
+        // the last elf counts without a blank line after it
+        let trimmed: Inventory = TEST_INPUT.trim_end().parse().unwrap();
+        assert_eq!(inventory, trimmed);
+        assert_eq!(5, trimmed.elves.len());
+        assert_eq!(Err(()), "1000\nlots\n".parse::<Inventory>());
+    }
+
+    #[test]
+    fn summary() {
+        let summary = TEST_INPUT.parse::<Inventory>().unwrap().summary();
+        assert_eq!(Some(10000), summary.median());
+        assert_eq!(Some(4000), summary.percentile(0.0));
+        assert_eq!(Some(24000), summary.percentile(100.0));
+        assert_eq!(vec![3, 1, 2, 3, 1], summary.item_counts);
+        assert_eq!(10, summary.items());
+        assert_eq!(Some(1), summary.fewest_items());
+        assert_eq!(Some(3), summary.most_items());
+        assert_eq!(
+            "5 elves, 10 items (1 to 3 per elf, 2.0 on average), median 10000, p90 24000",
+            summary.to_string()
+        );
     }
 
-    // find biggest elf
-    let part1 = elves
-        .iter()
-        .map(|v| v.iter().sum::<u32>())
-        .max()
-        .expect("at least one elf");
+    const TEST_INPUT: &str = "1000
+2000
+3000
 
-    // part 2
+4000
 
-    // collect the sums, then sort
-    let mut sums: Vec<u32> = elves.iter().map(|v| v.iter().sum::<u32>()).collect();
-    sums.sort_unstable();
-    sums.reverse();
+5000
+6000
 
-    // get the top 3
-    let &[a, b, c, ..] = sums.as_slice() else {
-            panic!("not even 3 elves?");
-        };
-    let part2 = a + b + c;
+7000
+8000
+9000
 
-    format!("{part1}, {part2}")
+10000
+";
 }
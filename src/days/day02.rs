@@ -1,80 +1,276 @@
+use crate::params;
+
+/// `-p day02.analyze=true` adds the best score the guide's opponent moves
+/// allow, and the moves that get it.
+///
+/// `-p day02.shapes=<a>,<b>,...` plays with one shape per score instead of
+/// rock, paper and scissors, and `-p day02.outcomes=<loss>,<draw>,<win>`
+/// changes what each outcome scores. More shapes than X, Y and Z need the
+/// second column to start earlier, e.g. `-p day02.second=V` for 5 shapes.
 pub fn solve(input: &str) -> String {
-    // part 1
-    let part1: u32 = input
-        .split('\n')
-        .map(|l| {
-            if l.is_empty() {
-                0
-            } else {
-                let (them, me) = l.split_once(' ').unwrap();
-                let shape_score = match me {
-                    "X" => 1,
-                    "Y" => 2,
-                    "Z" => 3,
-                    _ => panic!(),
-                };
-                let outcome_score = score(them.chars().next().unwrap(), me.chars().next().unwrap());
-                shape_score + outcome_score
-            }
+    let scores = |key| {
+        params::get::<String>(key).map(|list| {
+            list.split(',')
+                .map(|n| n.trim().parse::<u32>())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap_or_else(|_| panic!("{key} needs a list of scores"))
         })
+    };
+    let mut game = match scores("day02.shapes") {
+        Some(shapes) => Game::with_scores(shapes, [0, 3, 6])
+            .expect("day02.shapes needs an odd number of shapes, at least 3"),
+        None => Game::rock_paper_scissors(),
+    };
+    if let Some(outcomes) = scores("day02.outcomes") {
+        game.outcome_scores = outcomes
+            .try_into()
+            .expect("day02.outcomes needs a loss, draw and win score");
+    }
+    let second = params::get::<char>("day02.second").unwrap_or('X');
+    let second = u8::try_from(second)
+        .ok()
+        .filter(u8::is_ascii_uppercase)
+        .expect("day02.second needs a letter from A to Z");
+    let guide = Guide::parse(input, b'A', second, game.shapes()).unwrap_or_else(|| {
+        panic!(
+            "bad strategy guide, or {} shapes don't fit from {} to Z",
+            game.shapes(),
+            second as char
+        )
+    });
+
+    // part 1: the second column is our shape
+    let part1: u32 = guide
+        .rounds
+        .iter()
+        .map(|&(them, me)| game.round_score(me, them))
         .sum();
 
-    // part 2
-    let part2: u32 = input
-        .split('\n')
-        .map(|l| {
-            if l.is_empty() {
-                0
-            } else {
-                let (them, me) = l.split_once(' ').unwrap();
-                let outcome_score = match me {
-                    "X" => 0,
-                    "Y" => 3,
-                    "Z" => 6,
-                    _ => panic!(),
-                };
-                let shape = play_needed(them.chars().next().unwrap(), outcome_score);
-                let shape_score = match shape {
-                    'X' => 1,
-                    'Y' => 2,
-                    'Z' => 3,
-                    _ => panic!(),
-                };
-                shape_score + outcome_score
-            }
+    // part 2: the second column is how the round should end
+    let part2: u32 = guide
+        .outcomes()
+        .unwrap_or_else(|| {
+            let letters: String = (second..second + 3).map(char::from).collect();
+            panic!("part 2 needs the second column to be one of {letters}")
         })
+        .into_iter()
+        .map(|(them, outcome)| game.round_score(game.shape_for(them, outcome), them))
         .sum();
 
-    format!("{part1}, {part2}")
+    let mut out = format!("{part1}, {part2}");
+    if params::get::<bool>("day02.analyze").unwrap_or(false) {
+        out += &format!(" ({})", game.analyze(&guide));
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    const ALL: [Outcome; 3] = [Outcome::Loss, Outcome::Draw, Outcome::Win];
+}
+
+/// Rock paper scissors generalised to any odd number of shapes, where each
+/// shape beats the `(n - 1) / 2` shapes before it (wrapping round) and loses
+/// to the ones after it. Three shapes is the usual game, five is
+/// rock-paper-scissors-lizard-Spock and so on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Game {
+    shape_scores: Vec<u32>,
+    // loss, draw, win
+    outcome_scores: [u32; 3],
+}
+
+impl Game {
+    /// A game with one shape per score, or `None` unless there's an odd
+    /// number of them and at least 3. `outcome_scores` are for a loss, draw
+    /// and win.
+    fn with_scores(shape_scores: Vec<u32>, outcome_scores: [u32; 3]) -> Option<Self> {
+        let shapes = shape_scores.len();
+        (shapes >= 3 && shapes % 2 == 1).then_some(Self {
+            shape_scores,
+            outcome_scores,
+        })
+    }
+
+    /// Shapes scoring 1, 2, 3 and so on, and the usual 0, 3 or 6 for the
+    /// outcome.
+    fn new(shapes: usize) -> Self {
+        Self::with_scores((1..=shapes as u32).collect(), [0, 3, 6])
+            .expect("needs an odd number of shapes")
+    }
+
+    fn rock_paper_scissors() -> Self {
+        Self::new(3)
+    }
+
+    fn shapes(&self) -> usize {
+        self.shape_scores.len()
+    }
+
+    fn outcome(&self, me: usize, them: usize) -> Outcome {
+        let n = self.shapes();
+        match (me + n - them) % n {
+            0 => Outcome::Draw,
+            d if d <= n / 2 => Outcome::Win,
+            _ => Outcome::Loss,
+        }
+    }
+
+    fn round_score(&self, me: usize, them: usize) -> u32 {
+        self.shape_scores[me] + self.outcome_scores[self.outcome(me, them) as usize]
+    }
+
+    /// The best scoring shape that ends the round as `outcome`.
+    fn shape_for(&self, them: usize, outcome: Outcome) -> usize {
+        (0..self.shapes())
+            .filter(|&me| self.outcome(me, them) == outcome)
+            .max_by_key(|&me| self.shape_scores[me])
+            .unwrap()
+    }
+
+    /// Our best reply to every round of the guide, ignoring its advice.
+    fn analyze(&self, guide: &Guide) -> Analysis {
+        let choices: Vec<usize> = guide
+            .rounds
+            .iter()
+            .map(|&(them, _)| {
+                (0..self.shapes())
+                    .max_by_key(|&me| self.round_score(me, them))
+                    .unwrap()
+            })
+            .collect();
+        let best = guide
+            .rounds
+            .iter()
+            .zip(&choices)
+            .map(|(&(them, _), &me)| self.round_score(me, them))
+            .sum();
+        Analysis {
+            best,
+            choices,
+            letter: guide.second,
+        }
+    }
 }
 
-fn score(them: char, me: char) -> u32 {
-    let them = them as u8 - b'A';
-    let me = me as u8 - b'X';
-
-    match (them, me) {
-        // ties
-        (0, 0) => 3,
-        (1, 1) => 3,
-        (2, 2) => 3,
-        // wins
-        (0, 1) => 6,
-        (1, 2) => 6,
-        (2, 0) => 6,
-        // losses
-        (1, 0) => 0,
-        (2, 1) => 0,
-        (0, 2) => 0,
-        // all others are wat
-        _ => panic!(),
+/// The rounds of a strategy guide, as indexes from each column's first letter.
+/// Both columns can go up to the number of shapes, but no further than Z.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Guide {
+    rounds: Vec<(usize, usize)>,
+    second: u8,
+}
+
+impl Guide {
+    fn parse(input: &str, first: u8, second: u8, shapes: usize) -> Option<Self> {
+        // so every shape has a letter to write it with
+        let fits =
+            |start: u8| start.is_ascii_uppercase() && usize::from(b'Z' - start) + 1 >= shapes;
+        if !(fits(first) && fits(second)) {
+            return None;
+        }
+        let rounds = input
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| {
+                let (a, b) = l.trim().split_once(' ')?;
+                let a = a.as_bytes().first()?.checked_sub(first)?;
+                let b = b.as_bytes().first()?.checked_sub(second)?;
+                let (a, b) = (a as usize, b as usize);
+                (a < shapes && b < shapes).then_some((a, b))
+            })
+            .collect::<Option<_>>()?;
+        Some(Self { rounds, second })
+    }
+
+    /// The rounds with the second column read as how they should end, or
+    /// `None` if it goes past the 3 outcomes.
+    fn outcomes(&self) -> Option<Vec<(usize, Outcome)>> {
+        self.rounds
+            .iter()
+            .map(|&(them, end)| Some((them, *Outcome::ALL.get(end)?)))
+            .collect()
     }
 }
 
-fn play_needed(them: char, score_needed: u32) -> char {
-    for play in ['X', 'Y', 'Z'] {
-        if score(them, play) == score_needed {
-            return play;
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Analysis {
+    best: u32,
+    // our shape each round
+    choices: Vec<usize>,
+    // how to write our shapes, as in the guide
+    letter: u8,
+}
+
+impl std::fmt::Display for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let moves: String = self
+            .choices
+            .iter()
+            .map(|&c| (self.letter + c as u8) as char)
+            .collect();
+        write!(f, "best possible {}: {moves}", self.best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        assert_eq!("15, 12", solve(TEST_INPUT));
+
+        let game = Game::rock_paper_scissors();
+        let guide = Guide::parse(TEST_INPUT, b'A', b'X', 3).unwrap();
+        assert_eq!("best possible 24: YZX", game.analyze(&guide).to_string());
+        assert_eq!(None, Guide::parse("A W", b'A', b'X', 3));
+        assert_eq!(None, Guide::parse("D X", b'A', b'X', 3));
+    }
+
+    #[test]
+    fn bigger_games() {
+        for shapes in [3, 5, 7] {
+            let game = Game::new(shapes);
+            for them in 0..shapes {
+                let wins = (0..shapes).filter(|&me| game.outcome(me, them) == Outcome::Win);
+                assert_eq!(shapes / 2, wins.count());
+                // and it's the other way round for them
+                for me in 0..shapes {
+                    let (a, b) = (game.outcome(me, them), game.outcome(them, me));
+                    assert_eq!(a == Outcome::Win, b == Outcome::Loss);
+                }
+            }
         }
+
+        assert_eq!(None, Game::with_scores(vec![1, 2, 3, 4], [0, 3, 6]));
+        let game = Game::with_scores(vec![5, 1, 1, 1, 1], [0, 1, 2]).unwrap();
+        // losing with the big shape still beats winning with a small one
+        assert_eq!(5, game.round_score(0, 1));
+        assert_eq!(0, game.shape_for(1, Outcome::Loss));
+
+        // a fourth and fifth shape are fine for part 1, but not as outcomes
+        let guide = Guide::parse("A X\nE Z", b'A', b'V', game.shapes()).unwrap();
+        assert_eq!(vec![(0, 2), (4, 4)], guide.rounds);
+        assert_eq!(None, guide.outcomes());
+        let guide = Guide::parse("E V\nA X", b'A', b'V', game.shapes()).unwrap();
+        assert_eq!(
+            Some(vec![(4, Outcome::Loss), (0, Outcome::Win)]),
+            guide.outcomes()
+        );
+        assert_eq!("best possible 13: VV", game.analyze(&guide).to_string());
+        // X, Y and Z only have room for 3 shapes
+        assert_eq!(None, Guide::parse("A X", b'A', b'X', game.shapes()));
     }
-    panic!()
+
+    const TEST_INPUT: &str = "A Y
+B X
+C Z
+";
 }
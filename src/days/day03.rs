@@ -1,65 +1,219 @@
-use std::collections::HashSet;
+use crate::params;
+use std::fmt;
 
+/// `-p day03.group=<n>` changes the part 2 group size from 3, and
+/// `-p day03.report=true` adds which items each group shares and how many
+/// rucksacks hold each item.
 pub fn solve(input: &str) -> String {
-    let part1 = part1(&input);
-    let part2 = part2(&input);
-    format!("{part1}, {part2}")
+    let group_size = params::get("day03.group").unwrap_or(3);
+    let part1 = part1(input).unwrap();
+    let part2 = part2(input, group_size).unwrap();
+    let mut out = format!("{part1}, {part2}");
+    if params::get::<bool>("day03.report").unwrap_or(false) {
+        out += &format!("\n{}", report(input, group_size).unwrap());
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RucksackError {
+    /// Only ASCII letters are items.
+    BadItem(char),
+    /// Compartments have to be the same size.
+    OddLength(usize),
+    /// The rucksacks (or compartments) had nothing in common.
+    NoCommonItem,
+    /// There should be just the one item in common.
+    SeveralCommonItems(Items),
+    /// The last group is missing rucksacks.
+    IncompleteGroup(usize),
+    /// Groups need at least one rucksack.
+    EmptyGroups,
+}
+
+impl fmt::Display for RucksackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RucksackError::BadItem(c) => write!(f, "{c:?} isn't an item"),
+            RucksackError::OddLength(len) => write!(f, "can't split {len} items in half"),
+            RucksackError::NoCommonItem => write!(f, "no item in common"),
+            RucksackError::SeveralCommonItems(items) => write!(f, "several items in common: {items}"),
+            RucksackError::IncompleteGroup(len) => write!(f, "last group only has {len} rucksacks"),
+            RucksackError::EmptyGroups => write!(f, "groups can't be empty"),
+        }
+    }
+}
+
+/// A set of items, as a bitmask where bit `p - 1` is the item of priority `p`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Items(u64);
+
+impl Items {
+    const ALL: Items = Items((1 << 52) - 1);
+
+    fn parse(s: &str) -> Result<Items, RucksackError> {
+        s.chars().try_fold(Items(0), |items, c| {
+            Ok(Items(items.0 | 1 << (priority(c)? - 1)))
+        })
+    }
+
+    fn intersect(self, other: Items) -> Items {
+        Items(self.0 & other.0)
+    }
+
+    fn contains(self, priority: u8) -> bool {
+        self.0 & 1 << (priority - 1) != 0
+    }
+
+    /// The priority of the only item in the set.
+    fn single(self) -> Result<u8, RucksackError> {
+        match self.0.count_ones() {
+            0 => Err(RucksackError::NoCommonItem),
+            1 => Ok(self.0.trailing_zeros() as u8 + 1),
+            _ => Err(RucksackError::SeveralCommonItems(self)),
+        }
+    }
+}
+
+impl fmt::Display for Items {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for p in (1..=52).filter(|&p| self.contains(p)) {
+            write!(f, "{}", item(p))?;
+        }
+        Ok(())
+    }
 }
 
-fn part1(input: &str) -> String {
-    let sum: u64 = input
-        .lines()
-        .map(split)
-        .map(|(left, right)| common(left, right))
-        .map(|c| priority(c) as u64)
-        .sum();
+fn priority(c: char) -> Result<u8, RucksackError> {
+    match c {
+        'a'..='z' => Ok(c as u8 - b'a' + 1),
+        'A'..='Z' => Ok(c as u8 - b'A' + 27),
+        _ => Err(RucksackError::BadItem(c)),
+    }
+}
 
-    format!("{sum}")
+fn item(priority: u8) -> char {
+    match priority {
+        1..=26 => (b'a' + priority - 1) as char,
+        _ => (b'A' + priority - 27) as char,
+    }
 }
 
-fn split(input: &str) -> (&str, &str) {
-    let len = input.len() / 2;
-    (&input[0..len], &input[len..])
+fn common(rucksacks: &[Items]) -> Items {
+    rucksacks.iter().fold(Items::ALL, |a, &b| a.intersect(b))
 }
 
-fn common(a: &str, b: &str) -> char {
-    let a: HashSet<char> = a.chars().collect();
-    let b: HashSet<char> = b.chars().collect();
+fn split(line: &str) -> Result<(&str, &str), RucksackError> {
+    if !line.len().is_multiple_of(2) {
+        return Err(RucksackError::OddLength(line.len()));
+    }
+    // only ASCII is valid, and it's checked when parsing the halves, but
+    // don't split a multi-byte char
+    if !line.is_char_boundary(line.len() / 2) {
+        return Err(RucksackError::BadItem(line.chars().find(|c| !c.is_ascii()).unwrap()));
+    }
+    Ok(line.split_at(line.len() / 2))
+}
 
-    let common: HashSet<char> = &a & &b;
-    common.into_iter().next().unwrap()
+fn part1(input: &str) -> Result<u64, RucksackError> {
+    input.lines().try_fold(0, |sum, line| {
+        let (left, right) = split(line)?;
+        let both = common(&[Items::parse(left)?, Items::parse(right)?]);
+        Ok(sum + both.single()? as u64)
+    })
 }
 
-fn priority(c: char) -> u8 {
-    if c.is_ascii_lowercase() {
-        (c as u8) - b'a' + 1
-    } else {
-        (c as u8) - b'A' + 27
+fn groups(input: &str, size: usize) -> Result<Vec<Vec<Items>>, RucksackError> {
+    if size == 0 {
+        return Err(RucksackError::EmptyGroups);
+    }
+    let rucksacks = input.lines().map(Items::parse).collect::<Result<Vec<_>, _>>()?;
+    let groups: Vec<Vec<Items>> = rucksacks.chunks(size).map(<[Items]>::to_vec).collect();
+    match groups.last() {
+        Some(last) if last.len() < size => Err(RucksackError::IncompleteGroup(last.len())),
+        _ => Ok(groups),
     }
 }
 
-fn part2(input: &str) -> String {
-    let mut lines = input.lines();
-    let mut badges = Vec::new();
+fn part2(input: &str, group_size: usize) -> Result<u64, RucksackError> {
+    groups(input, group_size)?
+        .iter()
+        .try_fold(0, |sum, group| Ok(sum + common(group).single()? as u64))
+}
 
-    // loop one group at a time (3 lines)
-    loop {
-        let (Some(a), Some(b), Some(c)) = (lines.next(), lines.next(), lines.next()) else {
-            break;
-        };
-        let badge = common3(a, b, c);
-        badges.push(badge);
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Report {
+    // everything each group has in common
+    shared: Vec<Items>,
+    // how many rucksacks hold each item, indexed by priority - 1
+    frequency: [u32; 52],
+}
+
+fn report(input: &str, group_size: usize) -> Result<Report, RucksackError> {
+    let groups = groups(input, group_size)?;
+    let mut frequency = [0; 52];
+    for rucksack in groups.iter().flatten() {
+        for p in 1..=52 {
+            frequency[p as usize - 1] += rucksack.contains(p) as u32;
+        }
     }
+    Ok(Report {
+        shared: groups.iter().map(|g| common(g)).collect(),
+        frequency,
+    })
+}
 
-    let sum: u64 = badges.iter().map(|&b| priority(b) as u64).sum();
-    format!("{sum}")
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, shared) in self.shared.iter().enumerate() {
+            writeln!(f, "group {}: {shared}", i + 1)?;
+        }
+        let counts: Vec<String> = (1..=52)
+            .filter(|&p| self.frequency[p as usize - 1] > 0)
+            .map(|p| format!("{}={}", item(p), self.frequency[p as usize - 1]))
+            .collect();
+        write!(f, "items: {}", counts.join(" "))
+    }
 }
 
-fn common3(a: &str, b: &str, c: &str) -> char {
-    let a: HashSet<char> = a.chars().collect();
-    let b: HashSet<char> = b.chars().collect();
-    let c: HashSet<char> = c.chars().collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        assert_eq!(Ok(157), part1(TEST_INPUT));
+        assert_eq!(Ok(70), part2(TEST_INPUT, 3));
+        // nothing is in all six
+        assert_eq!(Err(RucksackError::NoCommonItem), part2(TEST_INPUT, 6));
+        assert_eq!(Err(RucksackError::IncompleteGroup(2)), part2(TEST_INPUT, 4));
+        assert_eq!(Err(RucksackError::EmptyGroups), part2(TEST_INPUT, 0));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Err(RucksackError::BadItem('1')), part1("ab1b"));
+        assert_eq!(Err(RucksackError::OddLength(3)), part1("aba"));
+        assert_eq!(Err(RucksackError::NoCommonItem), part1("abcd"));
+        let several = Items::parse("ab").unwrap();
+        assert_eq!(Err(RucksackError::SeveralCommonItems(several)), part1("abab"));
+    }
+
+    #[test]
+    fn reports() {
+        let report = report(TEST_INPUT, 3).unwrap();
+        assert_eq!("r", report.shared[0].to_string());
+        assert_eq!("Z", report.shared[1].to_string());
+        // r is in all but two rucksacks
+        assert_eq!(4, report.frequency[priority('r').unwrap() as usize - 1]);
+        assert!(report.to_string().starts_with("group 1: r\ngroup 2: Z\nitems: "));
+    }
 
-    let common: HashSet<char> = &(&a & &b) & &c;
-    common.into_iter().next().unwrap()
+    const TEST_INPUT: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw
+";
 }
@@ -1,14 +1,22 @@
+use crate::params;
+use std::fmt;
+
+/// `-p day04.analytics=true` adds a summary of how the sections are covered.
 pub fn solve(input: &str) -> String {
     let part1 = part1(&input);
     let part2 = part2(&input);
-    format!("{part1}, {part2}")
+    let mut out = format!("{part1}, {part2}");
+    if params::get::<bool>("day04.analytics").unwrap_or(false) {
+        out += &format!(" ({})", Assignments::parse(input).summary());
+    }
+    out
 }
 
 fn part1(input: &str) -> String {
     let count = input
         .trim()
         .lines()
-        .map(ranges)
+        .map(|line| ranges(line).expect("bad assignment"))
         .filter_map(|(a, b)| (a.fully_contains(&b) || b.fully_contains(&a)).then_some(()))
         .count();
     format!("{count}")
@@ -18,25 +26,27 @@ fn part2(input: &str) -> String {
     let count = input
         .trim()
         .lines()
-        .map(ranges)
+        .map(|line| ranges(line).expect("bad assignment"))
         .filter_map(|(a, b)| a.overlaps(&b).then_some(()))
         .count();
     format!("{count}")
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 struct Range {
     beg: u32,
     end: u32,
 }
 
 impl Range {
-    fn parse(s: &str) -> Range {
-        let (beg, end) = s.split_once('-').unwrap();
-        Range {
-            beg: beg.parse().unwrap(),
-            end: end.parse().unwrap(),
-        }
+    /// `None` unless it's `beg-end` with `beg <= end`.
+    fn parse(s: &str) -> Option<Range> {
+        let (beg, end) = s.split_once('-')?;
+        let range = Range {
+            beg: beg.parse().ok()?,
+            end: end.parse().ok()?,
+        };
+        (range.beg <= range.end).then_some(range)
     }
 
     fn fully_contains(&self, other: &Range) -> bool {
@@ -52,9 +62,274 @@ impl Range {
         }
         true
     }
+
+    /// How many sections both ranges cover.
+    fn overlap(&self, other: &Range) -> u64 {
+        let beg = self.beg.max(other.beg) as u64;
+        let end = self.end.min(other.end) as u64;
+        (end + 1).saturating_sub(beg)
+    }
+}
+
+fn ranges(line: &str) -> Option<(Range, Range)> {
+    let (a, b) = line.split_once(',')?;
+    Some((Range::parse(a)?, Range::parse(b)?))
+}
+
+/// Every elf's assignment at once. Elf `2 * i` and `2 * i + 1` are pair `i`.
+///
+/// Coverage questions go through one sweep over the range ends, so they're
+/// O(n log n) however much the ranges overlap.
+struct Assignments {
+    pairs: Vec<(Range, Range)>,
+    coverage: Coverage,
 }
 
-fn ranges(line: &str) -> (Range, Range) {
-    let (a, b) = line.split_once(',').unwrap();
-    (Range::parse(a), Range::parse(b))
+impl Assignments {
+    fn parse(input: &str) -> Self {
+        let pairs: Vec<_> = input
+            .trim()
+            .lines()
+            .map(|line| ranges(line).expect("bad assignment"))
+            .collect();
+        let coverage = Coverage::sweep(pairs.iter().flat_map(|&(a, b)| [a, b]));
+        Self { pairs, coverage }
+    }
+
+    fn elves(&self) -> impl Iterator<Item = Range> + '_ {
+        self.pairs.iter().flat_map(|&(a, b)| [a, b])
+    }
+
+    fn overlap_sizes(&self) -> Vec<u64> {
+        self.pairs.iter().map(|(a, b)| a.overlap(b)).collect()
+    }
+
+    /// Sections between the lowest and highest assigned that exactly `k`
+    /// elves cover, so `k = 0` counts the gaps.
+    fn covered_by(&self, k: u32) -> u64 {
+        self.coverage
+            .segments()
+            .filter(|&(_, count)| count == k)
+            .map(|(range, _)| (range.end - range.beg) as u64 + 1)
+            .sum()
+    }
+
+    fn max_coverage(&self) -> u32 {
+        self.coverage.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Elves whose every section someone else covers too.
+    fn redundant_elves(&self) -> Vec<usize> {
+        let min = MinTable::new(&self.coverage.counts);
+        self.elves()
+            .enumerate()
+            .filter(|(_, elf)| {
+                let (first, last) = self.coverage.segments_within(elf);
+                min.query(first, last) >= 2
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn summary(&self) -> Summary {
+        Summary {
+            overlapping_sections: self.overlap_sizes().iter().sum(),
+            uncovered: self.covered_by(0),
+            max_coverage: self.max_coverage(),
+            redundant: self.redundant_elves().len(),
+        }
+    }
+}
+
+/// How many elves cover each stretch of sections: segment `i` starts at
+/// `starts[i]` and runs up to the next start, with `counts[i]` elves on it.
+/// The last start is just past the highest section, which is why they're
+/// `u64`: it can be `u32::MAX + 1`.
+struct Coverage {
+    starts: Vec<u64>,
+    counts: Vec<u32>,
+}
+
+impl Coverage {
+    fn sweep(ranges: impl Iterator<Item = Range>) -> Self {
+        let mut events: Vec<(u64, i32)> = ranges
+            .flat_map(|r| [(r.beg as u64, 1), (r.end as u64 + 1, -1)])
+            .collect();
+        events.sort_unstable();
+
+        let mut starts = Vec::new();
+        let mut counts = Vec::new();
+        let mut count = 0;
+        for (i, &(at, delta)) in events.iter().enumerate() {
+            count += delta;
+            // only record once every event at this section is applied
+            if events.get(i + 1).is_none_or(|&(next, _)| next != at) {
+                starts.push(at);
+                counts.push(count as u32);
+            }
+        }
+        // the final segment is the empty space after everyone
+        counts.pop();
+        Self { starts, counts }
+    }
+
+    fn segments(&self) -> impl Iterator<Item = (Range, u32)> + '_ {
+        self.starts.windows(2).zip(&self.counts).map(|(w, &count)| {
+            // only the last start is past u32::MAX
+            let range = Range {
+                beg: w[0] as u32,
+                end: (w[1] - 1) as u32,
+            };
+            (range, count)
+        })
+    }
+
+    /// The first and last segments inside `range`, which must be one of the
+    /// swept ranges so it lines up with segment boundaries.
+    fn segments_within(&self, range: &Range) -> (usize, usize) {
+        let first = self.starts.partition_point(|&s| s <= range.beg as u64) - 1;
+        let last = self.starts.partition_point(|&s| s <= range.end as u64) - 1;
+        (first, last)
+    }
+}
+
+/// Sparse table for O(1) minimum over any run of values.
+struct MinTable {
+    // levels[k][i] is the min of values[i..i + 2^k]
+    levels: Vec<Vec<u32>>,
+}
+
+impl MinTable {
+    fn new(values: &[u32]) -> Self {
+        let mut levels = vec![values.to_vec()];
+        let mut width = 1;
+        while width * 2 <= values.len() {
+            let prev = levels.last().unwrap();
+            let next = (0..=values.len() - width * 2)
+                .map(|i| prev[i].min(prev[i + width]))
+                .collect();
+            levels.push(next);
+            width *= 2;
+        }
+        Self { levels }
+    }
+
+    // min of values[first..=last]
+    fn query(&self, first: usize, last: usize) -> u32 {
+        let level = (last - first + 1).ilog2() as usize;
+        let width = 1 << level;
+        self.levels[level][first].min(self.levels[level][last + 1 - width])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Summary {
+    overlapping_sections: u64,
+    uncovered: u64,
+    max_coverage: u32,
+    redundant: usize,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} sections overlap within pairs, {} uncovered, at most {} elves on one section, {} redundant elves",
+            self.overlapping_sections, self.uncovered, self.max_coverage, self.redundant
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn tests() {
+        assert_eq!("2", part1(TEST_INPUT));
+        assert_eq!("4", part2(TEST_INPUT));
+    }
+
+    #[test]
+    fn analytics() {
+        let assignments = Assignments::parse(TEST_INPUT);
+        assert_eq!(vec![0, 0, 1, 5, 1, 3], assignments.overlap_sizes());
+        // 2..=9 is all covered, by up to 8 elves on section 6
+        assert_eq!(0, assignments.covered_by(0));
+        assert_eq!(8, assignments.max_coverage());
+        assert_eq!(1, assignments.covered_by(8));
+        assert_eq!(2, assignments.covered_by(4));
+        // only 7-9 (elf 5) has section 9 to itself
+        assert_eq!(
+            vec![0, 1, 2, 3, 4, 6, 7, 8, 9, 10, 11],
+            assignments.redundant_elves()
+        );
+
+        let gappy = Assignments::parse("1-2,8-9\n1-1,2-2");
+        assert_eq!(5, gappy.covered_by(0));
+        assert_eq!(2, gappy.covered_by(1));
+        assert_eq!(vec![0, 2, 3], gappy.redundant_elves());
+
+        // the sweep goes one past the last section
+        let huge = Assignments::parse("0-4294967295,5-5\n4294967295-4294967295,7-7");
+        assert_eq!(vec![1, 0], huge.overlap_sizes());
+        assert_eq!(u32::MAX as u64 - 2, huge.covered_by(1));
+        assert_eq!(3, huge.covered_by(2));
+        assert_eq!(vec![1, 2, 3], huge.redundant_elves());
+    }
+
+    #[test]
+    fn bad_ranges() {
+        assert_eq!(None, ranges("5-3,1-2"));
+        assert_eq!(None, ranges("1-2,9-8"));
+        assert_eq!(None, ranges("1-2"));
+        assert_eq!(None, ranges("1-x,2-3"));
+        assert_eq!(
+            Some((Range { beg: 3, end: 3 }, Range { beg: 1, end: 2 })),
+            ranges("3-3,1-2")
+        );
+    }
+
+    #[test]
+    fn sweep_matches_brute_force() {
+        // lots of overlapping pairs
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut next = || rng.gen_range(0..200_u32);
+        let mut input = String::new();
+        for _ in 0..300 {
+            let (a, b) = (next(), next());
+            let (c, d) = (next(), next());
+            input += &format!("{}-{},{}-{}\n", a.min(b), a.max(b), c.min(d), c.max(d));
+        }
+        let assignments = Assignments::parse(&input);
+        let elves: Vec<Range> = assignments.elves().collect();
+
+        let mut counts = vec![0; 200];
+        for elf in &elves {
+            for s in elf.beg..=elf.end {
+                counts[s as usize] += 1;
+            }
+        }
+        let lowest = elves.iter().map(|e| e.beg).min().unwrap() as usize;
+        let highest = elves.iter().map(|e| e.end).max().unwrap() as usize;
+        for k in 0..10 {
+            let expected = counts[lowest..=highest].iter().filter(|&&c| c == k).count();
+            assert_eq!(expected as u64, assignments.covered_by(k));
+        }
+        assert_eq!(*counts.iter().max().unwrap(), assignments.max_coverage());
+
+        let redundant: Vec<usize> = (0..elves.len())
+            .filter(|&i| (elves[i].beg..=elves[i].end).all(|s| counts[s as usize] >= 2))
+            .collect();
+        assert_eq!(redundant, assignments.redundant_elves());
+    }
+
+    const TEST_INPUT: &str = "2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8
+";
 }
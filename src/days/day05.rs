@@ -1,97 +1,447 @@
+use crate::params;
+use std::fmt;
+use std::num::NonZeroUsize;
+
+/// `-p day05.batch=<n>` adds the tops for a crane that moves up to `n` crates
+/// at a time, and `-p day05.replay=<n>` adds a drawing of the yard after the
+/// first `n` moves of part 1.
 pub fn solve(input: &str) -> String {
-    let part1 = part1(&input);
-    let part2 = part2(&input);
-    format!("{part1}, {part2}")
-}
-
-fn parse_stacks(input: &str) -> Vec<Vec<char>> {
-    // determine number of stacks
-    let (crate_str, num_line) = input.split_once('1').unwrap();
-    let num_stacks: usize = num_line
-        .lines() // just get one line
-        .next()
-        .unwrap()
-        .split_whitespace() // split to get row names
-        .last()
-        .unwrap()
-        .parse() // parse the last row number to get length
-        .unwrap();
-    let mut stacks = vec![Vec::new(); num_stacks];
-
-    // put crates in their stacks
-    for crate_line in crate_str.lines().rev().skip(1) {
-        for (stack, c) in crate_line.chars().skip(1).step_by(4).enumerate() {
-            if c != ' ' {
-                stacks[stack].push(c);
+    let part1 = part1(input).unwrap();
+    let part2 = part2(input).unwrap();
+    let mut out = format!("{part1}, {part2}");
+    if let Some(n) = params::get::<usize>("day05.batch") {
+        let crane = Batched::new(n).expect("day05.batch needs to be at least 1");
+        out += &format!(", {n} at a time: {}", run(input, &crane).unwrap());
+    }
+    if let Some(n) = params::get::<usize>("day05.replay") {
+        let (yard, moves) = parse(input).unwrap();
+        let mut replay = Replay::new(yard, &CrateMover9000);
+        replay.run(&moves[..n.min(moves.len())], false).unwrap();
+        out += &format!("\n{}", replay.yard);
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum YardError {
+    /// A line of the drawing that isn't crates or stack numbers.
+    BadDrawing(String),
+    BadMove(String),
+    /// Stack numbers count from 1, as in the input.
+    NoSuchStack(usize),
+    NotEnoughCrates {
+        stack: usize,
+        wanted: usize,
+        has: usize,
+    },
+}
+
+impl fmt::Display for YardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YardError::BadDrawing(line) => write!(f, "bad drawing line {line:?}"),
+            YardError::BadMove(line) => write!(f, "bad move {line:?}"),
+            YardError::NoSuchStack(stack) => write!(f, "there's no stack {stack}"),
+            YardError::NotEnoughCrates { stack, wanted, has } => {
+                write!(f, "can't move {wanted} crates off stack {stack}, it has {has}")
             }
         }
     }
-
-    stacks
 }
 
+/// Move `count` crates, with stacks numbered from 1 as in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Move {
     count: usize,
     source: usize,
     dest: usize,
 }
 
-fn parse_commands(input: &str) -> Vec<Move> {
-    input
-        .trim()
-        .lines()
-        .map(|l| {
-            let (_, l) = l.split_once("move ").unwrap();
-            let (count, l) = l.split_once(" from ").unwrap();
-            let (source, dest) = l.split_once(" to ").unwrap();
-            Move {
-                count: count.parse().unwrap(),
-                source: source.parse::<usize>().unwrap() - 1,
-                dest: dest.parse::<usize>().unwrap() - 1,
-            }
+impl Move {
+    fn parse(line: &str) -> Result<Move, YardError> {
+        let bad = || YardError::BadMove(line.to_string());
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let ["move", count, "from", source, "to", dest] = words[..] else {
+            return Err(bad());
+        };
+        let number = |s: &str| s.parse::<usize>().map_err(|_| bad());
+        Ok(Move {
+            count: number(count)?,
+            source: number(source)?,
+            dest: number(dest)?,
         })
-        .collect()
+    }
+}
+
+/// How a crane moves a pile of crates from one stack to another.
+trait CraneModel {
+    /// The most crates it can pick up in one go, at least 1.
+    fn capacity(&self) -> usize;
+
+    /// Reorder `lifted` (bottom to top, as they sat on the source stack) into
+    /// the order they end up on the destination.
+    fn arrange(&self, lifted: &mut [char]) {
+        // the top batch is dropped first, so batches land upside down but
+        // each keeps its own order
+        lifted.reverse();
+        for batch in lifted.chunks_mut(self.capacity()) {
+            batch.reverse();
+        }
+    }
+
+    /// Undo `arrange`, turning crates as they landed back into the order
+    /// they were lifted in.
+    fn unarrange(&self, landed: &mut [char]) {
+        for batch in landed.chunks_mut(self.capacity()) {
+            batch.reverse();
+        }
+        landed.reverse();
+    }
+}
+
+/// Moves one crate at a time.
+struct CrateMover9000;
+
+impl CraneModel for CrateMover9000 {
+    fn capacity(&self) -> usize {
+        1
+    }
+}
+
+/// Moves any number of crates at once.
+struct CrateMover9001;
+
+impl CraneModel for CrateMover9001 {
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+}
+
+/// Moves up to this many crates at a time.
+struct Batched(NonZeroUsize);
+
+impl Batched {
+    fn new(capacity: usize) -> Option<Self> {
+        NonZeroUsize::new(capacity).map(Batched)
+    }
+}
+
+impl CraneModel for Batched {
+    fn capacity(&self) -> usize {
+        self.0.get()
+    }
+}
+
+/// The stacks of crates, each bottom to top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CrateYard {
+    stacks: Vec<Vec<char>>,
+}
+
+impl CrateYard {
+    /// Parse the drawing of the stacks, including the stack numbers on the
+    /// last line.
+    fn parse(drawing: &str) -> Result<CrateYard, YardError> {
+        let mut lines: Vec<&str> = drawing.lines().filter(|l| !l.trim().is_empty()).collect();
+        let numbers = lines.pop().ok_or(YardError::BadDrawing(String::new()))?;
+
+        // the stacks should be numbered 1 to n in order
+        let bad_numbers = || YardError::BadDrawing(numbers.to_string());
+        let mut count = 0;
+        for n in numbers.split_whitespace() {
+            if n.parse::<usize>().map_err(|_| bad_numbers())? != count + 1 {
+                return Err(bad_numbers());
+            }
+            count += 1;
+        }
+
+        let mut stacks = vec![Vec::new(); count];
+        for line in lines.iter().rev() {
+            let bad = || YardError::BadDrawing(line.to_string());
+            // each stack is 3 chars wide, with a space between
+            for (stack, cell) in line.as_bytes().chunks(4).enumerate() {
+                match cell {
+                    [b' ', b' ', b' '] | [b' ', b' ', b' ', b' '] => {}
+                    [b'[', c, b']'] | [b'[', c, b']', b' '] if stack < count => {
+                        stacks[stack].push(*c as char)
+                    }
+                    _ => return Err(bad()),
+                }
+            }
+        }
+
+        Ok(CrateYard { stacks })
+    }
+
+    /// Make a move with the given crane, or leave the yard alone if the move
+    /// can't be made.
+    fn apply(&mut self, mov: Move, crane: &impl CraneModel) -> Result<(), YardError> {
+        let source = self.index(mov.source)?;
+        let dest = self.index(mov.dest)?;
+        let has = self.stacks[source].len();
+        if mov.count > has {
+            return Err(YardError::NotEnoughCrates {
+                stack: mov.source,
+                wanted: mov.count,
+                has,
+            });
+        }
+
+        let mut lifted = self.stacks[source].split_off(has - mov.count);
+        crane.arrange(&mut lifted);
+        self.stacks[dest].extend(lifted);
+        Ok(())
+    }
+
+    /// Put back a move made by the same crane.
+    fn undo(&mut self, mov: Move, crane: &impl CraneModel) -> Result<(), YardError> {
+        let undo = Move {
+            count: mov.count,
+            source: mov.dest,
+            dest: mov.source,
+        };
+        let source = self.index(undo.source)?;
+        let dest = self.index(undo.dest)?;
+        let has = self.stacks[source].len();
+        if undo.count > has {
+            return Err(YardError::NotEnoughCrates {
+                stack: undo.source,
+                wanted: undo.count,
+                has,
+            });
+        }
+
+        let mut landed = self.stacks[source].split_off(has - undo.count);
+        crane.unarrange(&mut landed);
+        self.stacks[dest].extend(landed);
+        Ok(())
+    }
+
+    // stack number to index
+    fn index(&self, stack: usize) -> Result<usize, YardError> {
+        match stack.checked_sub(1) {
+            Some(i) if i < self.stacks.len() => Ok(i),
+            _ => Err(YardError::NoSuchStack(stack)),
+        }
+    }
+
+    /// The top crate of each stack, skipping empty stacks.
+    fn tops(&self) -> String {
+        self.stacks.iter().filter_map(|s| s.last()).collect()
+    }
+}
+
+/// Draws the yard the way the puzzle input does, so it parses back the same.
+impl fmt::Display for CrateYard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        for row in (0..height).rev() {
+            let cells: Vec<String> = self
+                .stacks
+                .iter()
+                .map(|s| s.get(row).map_or("   ".to_string(), |c| format!("[{c}]")))
+                .collect();
+            writeln!(f, "{}", cells.join(" "))?;
+        }
+        let numbers: Vec<String> = (1..=self.stacks.len()).map(|n| format!("{n:^3}")).collect();
+        write!(f, "{}", numbers.join(" "))
+    }
 }
 
-fn part1(input: &str) -> String {
-    let (init_state, commands) = input.split_once("\n\n").unwrap();
-    let mut stacks = parse_stacks(init_state);
-    let commands = parse_commands(commands);
+/// Steps through moves on a yard, remembering them so they can be undone.
+struct Replay<'a, C> {
+    yard: CrateYard,
+    crane: &'a C,
+    done: Vec<Move>,
+}
+
+impl<'a, C: CraneModel> Replay<'a, C> {
+    fn new(yard: CrateYard, crane: &'a C) -> Self {
+        Replay {
+            yard,
+            crane,
+            done: Vec::new(),
+        }
+    }
+
+    fn step(&mut self, mov: Move) -> Result<(), YardError> {
+        self.yard.apply(mov, self.crane)?;
+        self.done.push(mov);
+        Ok(())
+    }
+
+    /// Take back the last move, if there is one.
+    fn undo(&mut self) -> Option<Move> {
+        let mov = self.done.pop()?;
+        // it was made, so it can always be put back
+        self.yard.undo(mov, self.crane).unwrap();
+        Some(mov)
+    }
 
-    for mov in commands {
-        for _ in 0..mov.count {
-            let krate = stacks[mov.source].pop().unwrap();
-            stacks[mov.dest].push(krate);
+    /// Make each move in turn, with a copy of the yard after each one if
+    /// `snapshots` is set. If a move can't be made, the ones before it are
+    /// undone and the error comes with its index in `moves`.
+    fn run(&mut self, moves: &[Move], snapshots: bool) -> Result<Vec<CrateYard>, (usize, YardError)> {
+        let mut yards = Vec::new();
+        for (i, &mov) in moves.iter().enumerate() {
+            if let Err(e) = self.step(mov) {
+                for _ in 0..i {
+                    self.undo();
+                }
+                return Err((i, e));
+            }
+            if snapshots {
+                yards.push(self.yard.clone());
+            }
         }
+        Ok(yards)
+    }
+}
+
+fn parse(input: &str) -> Result<(CrateYard, Vec<Move>), YardError> {
+    let (drawing, moves) = input
+        .split_once("\n\n")
+        .ok_or(YardError::BadDrawing(input.to_string()))?;
+    let yard = CrateYard::parse(drawing)?;
+    let moves = moves
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(Move::parse)
+        .collect::<Result<_, _>>()?;
+    Ok((yard, moves))
+}
+
+fn run(input: &str, crane: &impl CraneModel) -> Result<String, YardError> {
+    let (mut yard, moves) = parse(input)?;
+    for mov in moves {
+        yard.apply(mov, crane)?;
     }
+    Ok(yard.tops())
+}
+
+fn part1(input: &str) -> Result<String, YardError> {
+    run(input, &CrateMover9000)
+}
 
-    // get top crates
-    stacks.iter().map(|v| v.last().unwrap()).collect()
+fn part2(input: &str) -> Result<String, YardError> {
+    run(input, &CrateMover9001)
 }
 
-fn part2(input: &str) -> String {
-    let (init_state, commands) = input.split_once("\n\n").unwrap();
-    let mut stacks = parse_stacks(init_state);
-    let commands = parse_commands(commands);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        assert_eq!(Ok("CMZ".to_string()), part1(TEST_INPUT));
+        assert_eq!(Ok("MCD".to_string()), part2(TEST_INPUT));
+        let one_at_a_time = Batched::new(1).unwrap();
+        assert_eq!(Ok("CMZ".to_string()), run(TEST_INPUT, &one_at_a_time));
+        assert!(Batched::new(0).is_none());
+    }
+
+    #[test]
+    fn batches() {
+        let (mut yard, _) = parse(TEST_INPUT).unwrap();
+        yard.apply(Move::parse("move 1 from 2 to 1").unwrap(), &CrateMover9000)
+            .unwrap();
+        // stack 1 is now Z N D, moving 3 two at a time drops N D then Z
+        let two_at_a_time = Batched::new(2).unwrap();
+        yard.apply(Move::parse("move 3 from 1 to 3").unwrap(), &two_at_a_time)
+            .unwrap();
+        assert_eq!(vec!['P', 'N', 'D', 'Z'], yard.stacks[2]);
+        // and the empty stack is skipped
+        assert_eq!("CZ", yard.tops());
+    }
+
+    #[test]
+    fn errors() {
+        let (mut yard, _) = parse(TEST_INPUT).unwrap();
+        let before = yard.clone();
+        let mov = |s| Move::parse(s).unwrap();
+        assert_eq!(
+            Err(YardError::NoSuchStack(4)),
+            yard.apply(mov("move 1 from 4 to 1"), &CrateMover9000)
+        );
+        assert_eq!(
+            Err(YardError::NoSuchStack(0)),
+            yard.apply(mov("move 1 from 1 to 0"), &CrateMover9000)
+        );
+        assert_eq!(
+            Err(YardError::NotEnoughCrates {
+                stack: 3,
+                wanted: 2,
+                has: 1
+            }),
+            yard.apply(mov("move 2 from 3 to 1"), &CrateMover9000)
+        );
+        assert_eq!(before, yard);
+
+        assert!(matches!(Move::parse("move 1 from 2"), Err(YardError::BadMove(_))));
+        assert!(matches!(CrateYard::parse("[A]\n 2"), Err(YardError::BadDrawing(_))));
+        assert!(matches!(CrateYard::parse("[A] [B]\n 1"), Err(YardError::BadDrawing(_))));
+    }
+
+    #[test]
+    fn crates_labelled_with_numbers() {
+        let yard = CrateYard::parse("[1]    \n[2] [3]\n 1   2 ").unwrap();
+        assert_eq!(vec![vec!['2', '1'], vec!['3']], yard.stacks);
+    }
+
+    #[test]
+    fn drawings() {
+        let (yard, _) = parse(TEST_INPUT).unwrap();
+        // the puzzle input pads every line to the full width
+        let drawing = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 ";
+        assert_eq!(drawing, yard.to_string());
+        assert_eq!(Ok(yard), CrateYard::parse(drawing));
+
+        let empty = CrateYard {
+            stacks: vec![vec![], vec!['1']],
+        };
+        assert_eq!("    [1]\n 1   2 ", empty.to_string());
+        assert_eq!(Ok(empty.clone()), CrateYard::parse(&empty.to_string()));
+    }
+
+    #[test]
+    fn replay() {
+        let (yard, moves) = parse(TEST_INPUT).unwrap();
+        let mut replay = Replay::new(yard.clone(), &CrateMover9000);
+        let yards = replay.run(&moves, true).unwrap();
+        assert_eq!(4, yards.len());
+        assert_eq!("[D]        \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 ", yards[0].to_string());
+        assert_eq!("CMZ", replay.yard.tops());
 
-    for mov in commands {
-        // get the source and dest vectors out of stacks (prevents multiple &mut)
-        let mut source = Vec::new();
-        let mut dest = Vec::new();
-        std::mem::swap(&mut stacks[mov.source], &mut source);
-        std::mem::swap(&mut stacks[mov.dest], &mut dest);
+        while replay.undo().is_some() {}
+        assert_eq!(yard, replay.yard);
 
-        let krates = &source[source.len() - mov.count..];
-        // put crates in new place
-        dest.extend_from_slice(krates);
-        // remove crates from old
-        source.truncate(source.len() - mov.count);
+        // undo puts batches back the right way round too
+        let crane = Batched::new(2).unwrap();
+        let mut replay = Replay::new(yard.clone(), &crane);
+        replay.run(&moves[..2], false).unwrap();
+        assert_eq!(Some(moves[1]), replay.undo());
+        replay.undo();
+        assert_eq!(yard, replay.yard);
 
-        // put src, dest back in stacks
-        std::mem::swap(&mut stacks[mov.source], &mut source);
-        std::mem::swap(&mut stacks[mov.dest], &mut dest);
+        // a bad move stops the replay and rolls back the moves before it
+        let bad = Move::parse("move 9 from 1 to 2").unwrap();
+        let mut replay = Replay::new(yard.clone(), &CrateMover9001);
+        replay.step(moves[0]).unwrap();
+        let err = replay.run(&[moves[1], bad], true).unwrap_err();
+        assert_eq!(1, err.0);
+        assert_eq!(vec![moves[0]], replay.done);
+        replay.undo();
+        assert_eq!(yard, replay.yard);
     }
 
-    // get top crates
-    stacks.iter().map(|v| v.last().unwrap()).collect()
+    const TEST_INPUT: &str = "    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2
+";
 }
@@ -1,30 +1,112 @@
+use crate::params;
+use std::io;
+
+/// `-p day06.window=<n>` adds the first marker of `n` different characters
+/// and how many there are in all.
 pub fn solve(input: &str) -> String {
-    let input = input.trim();
-    let p1 = part1(input);
-    let p2 = part2(input);
-    format!("{p1}, {p2}")
+    let input = input.trim().as_bytes();
+    let p1 = part1(input).expect("no start-of-packet marker");
+    let p2 = part2(input).expect("no start-of-message marker");
+    let mut out = format!("{p1}, {p2}");
+    if let Some(n) = params::get::<usize>("day06.window") {
+        // a slice is a reader that can't fail
+        let all = read_markers(input, n).unwrap();
+        let first = all.first().map_or("none".to_string(), |m| m.to_string());
+        out += &format!(", window {n}: first {first} of {}", all.len());
+    }
+    out
+}
+
+fn part1(input: &[u8]) -> Option<usize> {
+    first_marker(input, 4)
 }
 
-fn part1(input: &str) -> usize {
-    find_preamble(input, 4)
+fn part2(input: &[u8]) -> Option<usize> {
+    first_marker(input, 14)
 }
 
-fn part2(input: &str) -> usize {
-    find_preamble(input, 14)
+/// Finds markers, the runs of `size` different bytes, in a stream that's fed
+/// in a bit at a time. A marker's position is how many bytes have been
+/// received by the end of it.
+///
+/// Each byte is counted in as it arrives and out again `size` bytes later,
+/// so this is O(1) per byte whatever the window size.
+#[derive(Debug, Clone)]
+struct Detector {
+    // the last `size` bytes, as a ring indexed by position
+    window: Vec<u8>,
+    counts: [u32; 256],
+    // how many bytes have a count of exactly 1
+    unique: usize,
+    position: usize,
+}
+
+impl Detector {
+    fn new(size: usize) -> Self {
+        assert!(size > 0, "markers need at least one byte");
+        Detector {
+            window: vec![0; size],
+            counts: [0; 256],
+            unique: 0,
+            position: 0,
+        }
+    }
+
+    /// Take the next byte, and say whether it ends a marker.
+    fn push(&mut self, byte: u8) -> bool {
+        let size = self.window.len();
+        let slot = self.position % size;
+        if self.position >= size {
+            let old = self.window[slot] as usize;
+            self.counts[old] -= 1;
+            match self.counts[old] {
+                0 => self.unique -= 1,
+                1 => self.unique += 1,
+                _ => {}
+            }
+        }
+        self.window[slot] = byte;
+        self.counts[byte as usize] += 1;
+        match self.counts[byte as usize] {
+            1 => self.unique += 1,
+            2 => self.unique -= 1,
+            _ => {}
+        }
+        self.position += 1;
+        self.unique == size
+    }
+
+    /// Take the next chunk of the stream, giving the positions of the
+    /// markers that end in it.
+    fn feed(&mut self, chunk: &[u8]) -> Vec<usize> {
+        chunk
+            .iter()
+            .filter_map(|&b| self.push(b).then_some(self.position))
+            .collect()
+    }
 }
 
-fn find_preamble(input: &str, window_size: usize) -> usize {
-    for (index, window) in input.as_bytes().windows(window_size).enumerate() {
-        // first valid window starts after window_size chars are already received
-        // this window index is 0, but the char index would be window_size
-        let index = index + window_size;
+fn first_marker(input: &[u8], size: usize) -> Option<usize> {
+    let mut detector = Detector::new(size);
+    input
+        .iter()
+        .position(|&b| detector.push(b))
+        .map(|i| i + 1)
+}
 
-        let set: std::collections::HashSet<&u8> = window.iter().collect();
-        if set.len() == window_size {
-            return index;
+/// Every marker in a stream, read to the end.
+fn read_markers(mut reader: impl io::Read, size: usize) -> io::Result<Vec<usize>> {
+    let mut detector = Detector::new(size);
+    let mut found = Vec::new();
+    let mut buf = [0; 4096];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(found),
+            Ok(n) => found.extend(detector.feed(&buf[..n])),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
         }
     }
-    panic!()
 }
 
 #[cfg(test)]
@@ -33,7 +115,58 @@ mod tests {
 
     #[test]
     fn tests() {
-        assert_eq!(5, part1("bvwbjplbgvbhsrlpgdmjqwftvncz"));
-        assert_eq!(19, part2("mjqjpqmgbljsphdztnvjfqwrcgsmlb"));
+        for (input, p1, p2) in TEST_INPUTS {
+            assert_eq!(Some(p1), part1(input.as_bytes()));
+            assert_eq!(Some(p2), part2(input.as_bytes()));
+        }
+        assert_eq!(None, part1(b"abcabcabc"));
+        assert_eq!(None, part1(b"abc"));
+    }
+
+    #[test]
+    fn all_markers() {
+        let input = TEST_INPUTS[0].0.as_bytes();
+        // the same as checking every window by hand
+        for size in 1..=input.len() + 1 {
+            let naive: Vec<usize> = input
+                .windows(size)
+                .enumerate()
+                .filter(|(_, w)| (0..size).all(|i| !w[i + 1..].contains(&w[i])))
+                .map(|(i, _)| i + size)
+                .collect();
+            assert_eq!(naive, Detector::new(size).feed(input), "size {size}");
+        }
+    }
+
+    #[test]
+    fn streams() {
+        let input = TEST_INPUTS[1].0.as_bytes();
+        let whole = Detector::new(4).feed(input);
+
+        // chunks fed one by one find the same markers as all at once
+        for chunk in 1..input.len() {
+            let mut detector = Detector::new(4);
+            let found: Vec<usize> = input.chunks(chunk).flat_map(|c| detector.feed(c)).collect();
+            assert_eq!(whole, found, "chunks of {chunk}");
+        }
+
+        // a reader that only hands over a few bytes at a time
+        struct Trickle<'a>(&'a [u8]);
+        impl io::Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let n = buf.len().min(self.0.len()).min(3);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+        assert_eq!(whole, read_markers(Trickle(input), 4).unwrap());
     }
+
+    const TEST_INPUTS: [(&str, usize, usize); 4] = [
+        ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+        ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+        ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+        ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+    ];
 }
@@ -1,242 +1,763 @@
+use crate::params;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Asking "what if": `-p day07.rm=<path>` removes something and
+/// `-p day07.mv=<from>,<to>` moves something before answering. Then
+/// `-p day07.tree=true` adds a `tree`-style listing of the filesystem, and
+/// `-p day07.record=true` a shortest session that would list it.
 pub fn solve(input: &str) -> String {
-    let input = input.trim();
-    let p1 = part1(input);
-    let p2 = part2(input);
-    format!("{p1}, {p2}")
+    let mut fs = Vfs::from_transcript(input).unwrap_or_else(|_| {
+        let problems: Vec<String> = validate(input).iter().map(ToString::to_string).collect();
+        panic!("bad transcript\n{}", problems.join("\n"))
+    });
+    if let Some(path) = params::get::<String>("day07.rm") {
+        fs.rm(&path).unwrap();
+    }
+    if let Some(paths) = params::get::<String>("day07.mv") {
+        let (from, to) = paths.split_once(',').expect("day07.mv needs <from>,<to>");
+        fs.mv(from, to).unwrap();
+    }
+    let p1 = part1(&fs);
+    let p2 = part2(&fs);
+    let mut out = format!("{p1}, {p2}");
+    if params::get::<bool>("day07.tree").unwrap_or(false) {
+        out += &format!("\n{fs}");
+    }
+    if params::get::<bool>("day07.record").unwrap_or(false) {
+        out += &format!("\n{}", fs.transcript().trim_end());
+    }
+    out
 }
 
 const FILESYSTEM_SIZE: usize = 70_000_000;
 const SPACE_NEEDED: usize = 30_000_000;
 
-#[derive(Debug, PartialEq, Eq)]
-enum Inode {
-    Dir {
-        name: String,
-        size: usize,
-        inodes: Vec<Inode>,
-    },
-    File {
-        name: String,
-        size: usize,
-    },
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FsError {
+    /// A line that isn't a command, or output where there shouldn't be any.
+    BadLine(String),
+    UnknownCommand(String),
+    NotFound(String),
+    NotADir(String),
+    /// `ls` listed something differently from before.
+    Conflict(String),
+    AlreadyExists(String),
+    /// The root can't be removed or moved.
+    Root,
+    /// A directory can't be moved inside itself.
+    IntoItself(String),
+    /// `cd ..` in the root.
+    AboveRoot,
+}
+
+impl fmt::Display for FsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FsError::BadLine(line) => write!(f, "unexpected line {line:?}"),
+            FsError::UnknownCommand(cmd) => write!(f, "unknown command {cmd:?}"),
+            FsError::NotFound(path) => write!(f, "{path}: no such file or directory"),
+            FsError::NotADir(path) => write!(f, "{path}: not a directory"),
+            FsError::Conflict(path) => write!(f, "{path}: listed differently before"),
+            FsError::AlreadyExists(path) => write!(f, "{path}: already exists"),
+            FsError::Root => write!(f, "can't remove or move /"),
+            FsError::IntoItself(path) => write!(f, "can't move {path} inside itself"),
+            FsError::AboveRoot => write!(f, "can't cd above /"),
+        }
+    }
 }
 
-#[derive(Debug)]
+/// A line of `ls` output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Listing {
+    Dir { name: String },
+    File { name: String, size: usize },
+}
+
+impl core::str::FromStr for Listing {
+    type Err = FsError;
+    fn from_str(line: &str) -> Result<Self, FsError> {
+        let bad = || FsError::BadLine(line.into());
+        let (first, name) = line.split_once(' ').ok_or_else(bad)?;
+        if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+            return Err(bad());
+        }
+        Ok(match first {
+            "dir" => Listing::Dir { name: name.into() },
+            size => Listing::File {
+                name: name.into(),
+                size: size.parse().map_err(|_| bad())?,
+            },
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
 enum DirTarget {
     Root,
     Named { name: String },
     Up,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 enum Command {
     ChangeDir { to: DirTarget },
     List,
 }
 
-impl Inode {
-    fn cd<'a>(&'a mut self, to: &[impl AsRef<str>]) -> Option<&'a mut Self> {
-        if to.is_empty() {
-            return Some(self);
+impl core::str::FromStr for DirTarget {
+    type Err = ();
+    fn from_str(target: &str) -> Result<Self, ()> {
+        Ok(match target {
+            "/" => Self::Root,
+            ".." => DirTarget::Up,
+            name => DirTarget::Named { name: name.into() },
+        })
+    }
+}
+
+impl core::str::FromStr for Command {
+    type Err = FsError;
+    fn from_str(line: &str) -> Result<Self, FsError> {
+        let cmd_str = line
+            .strip_prefix("$ ")
+            .ok_or_else(|| FsError::BadLine(line.into()))?;
+
+        if let Some(target) = cmd_str.strip_prefix("cd ") {
+            Ok(Self::ChangeDir {
+                to: target.parse().unwrap(),
+            })
+        } else if cmd_str == "ls" {
+            Ok(Command::List)
+        } else {
+            Err(FsError::UnknownCommand(cmd_str.into()))
         }
+    }
+}
 
-        let inodes = match self {
-            Inode::Dir { inodes, .. } => inodes,
-            _ => {
-                panic!("can't cd from a file");
-            }
-        };
+type NodeId = usize;
+
+const ROOT: NodeId = 0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node {
+    name: String,
+    // the root is its own parent
+    parent: NodeId,
+    // a file's size, or the total of everything in a directory
+    size: usize,
+    // a directory's contents, by name
+    children: Option<BTreeMap<String, NodeId>>,
+}
+
+/// Something `find` found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry<'a> {
+    path: String,
+    name: &'a str,
+    size: usize,
+    is_dir: bool,
+}
+
+/// A filesystem addressed by absolute paths like `/a/e`, where every
+/// directory keeps the total size of what's in it up to date.
+///
+/// Nodes live in one `Vec`, so removing something just unlinks it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Vfs {
+    nodes: Vec<Node>,
+}
+
+impl Vfs {
+    fn new() -> Self {
+        Vfs {
+            nodes: vec![Node {
+                name: String::new(),
+                parent: ROOT,
+                size: 0,
+                children: Some(BTreeMap::new()),
+            }],
+        }
+    }
 
-        let next = to[0].as_ref();
-        let rest = &to[1..];
+    /// Rebuild the filesystem from a terminal session of `cd` and `ls`.
+    /// Listing the same directory again is fine, as long as it agrees.
+    /// `validate` says where all the problems are.
+    fn from_transcript(input: &str) -> Result<Self, FsError> {
+        let mut session = Session::new();
+        for line in input.lines() {
+            session.line(line)?;
+        }
+        Ok(session.fs)
+    }
 
-        for inode in inodes.iter_mut() {
-            if let Self::Dir { name, .. } = inode {
-                if name == next {
-                    return inode.cd(rest);
+    /// A shortest session that rebuilds this filesystem, starting in `/`.
+    /// Empty directories are listed but never visited.
+    fn transcript(&self) -> String {
+        let mut out = String::new();
+        // the directories from the root down to where we are
+        let mut cwd: Vec<NodeId> = Vec::new();
+        for id in self.walk() {
+            let children = match &self.nodes[id].children {
+                Some(children) if !children.is_empty() => children,
+                _ => continue,
+            };
+
+            let mut path = Vec::new();
+            let mut up = id;
+            while up != ROOT {
+                path.push(up);
+                up = self.nodes[up].parent;
+            }
+            path.reverse();
+
+            // either back up to where the paths meet, or start again from /
+            let shared = cwd.iter().zip(&path).take_while(|(a, b)| a == b).count();
+            let up = cwd.len() - shared;
+            let from = if 1 + shared < up {
+                out += "$ cd /\n";
+                0
+            } else {
+                out += &"$ cd ..\n".repeat(up);
+                shared
+            };
+            for &dir in &path[from..] {
+                out += &format!("$ cd {}\n", self.nodes[dir].name);
+            }
+            cwd = path;
+
+            out += "$ ls\n";
+            for (name, &child) in children {
+                match self.nodes[child].children {
+                    Some(_) => out += &format!("dir {name}\n"),
+                    None => out += &format!("{} {name}\n", self.nodes[child].size),
                 }
             }
         }
+        out
+    }
+
+    fn path(&self, mut id: NodeId) -> String {
+        let mut names = Vec::new();
+        while id != ROOT {
+            names.push(self.nodes[id].name.as_str());
+            id = self.nodes[id].parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
 
-        None
+    fn dir(&self, id: NodeId) -> Result<&BTreeMap<String, NodeId>, FsError> {
+        self.nodes[id]
+            .children
+            .as_ref()
+            .ok_or_else(|| FsError::NotADir(self.path(id)))
     }
 
-    fn push_inode(&mut self, inode: Inode) {
-        if let Self::Dir { inodes, .. } = self {
-            inodes.push(inode);
-        } else {
-            panic!("can't add stuff to a file")
+    fn child(&self, dir: NodeId, name: &str) -> Result<NodeId, FsError> {
+        match self.dir(dir)?.get(name) {
+            Some(&id) => Ok(id),
+            None => Err(FsError::NotFound(self.join(dir, name))),
         }
     }
 
-    fn compute_size(&mut self) -> usize {
-        match self {
-            Inode::Dir { inodes, size, .. } => {
-                *size = inodes.iter_mut().map(Self::compute_size).sum();
-                *size
-            }
-            Inode::File { size, .. } => *size,
+    fn join(&self, dir: NodeId, name: &str) -> String {
+        match dir {
+            ROOT => format!("/{name}"),
+            _ => format!("{}/{name}", self.path(dir)),
         }
     }
-}
 
-impl core::str::FromStr for Inode {
-    type Err = ();
-    fn from_str(line: &str) -> Result<Self, ()> {
-        if let Some((_, dir_name)) = line.split_once("dir ") {
-            return Ok(Self::Dir {
-                name: dir_name.into(),
-                size: 0,
-                inodes: Vec::new(),
-            });
+    /// Find an absolute path, which may go up with `..`.
+    fn lookup(&self, path: &str) -> Result<NodeId, FsError> {
+        let rest = path
+            .strip_prefix('/')
+            .ok_or_else(|| FsError::NotFound(path.into()))?;
+        rest.split('/')
+            .filter(|s| !s.is_empty() && *s != ".")
+            .try_fold(ROOT, |id, name| match name {
+                ".." => Ok(self.nodes[id].parent),
+                _ => self.child(id, name),
+            })
+    }
+
+    fn size(&self, path: &str) -> Result<usize, FsError> {
+        Ok(self.nodes[self.lookup(path)?].size)
+    }
+
+    /// Add a line of `ls` output to a directory, unless it's already there.
+    fn add(&mut self, dir: NodeId, listing: Listing) -> Result<NodeId, FsError> {
+        let (name, size, is_dir) = match listing {
+            Listing::Dir { name } => (name, 0, true),
+            Listing::File { name, size } => (name, size, false),
+        };
+        if let Some(&id) = self.dir(dir)?.get(&name) {
+            let node = &self.nodes[id];
+            return match node.children.is_some() == is_dir && (is_dir || node.size == size) {
+                true => Ok(id),
+                false => Err(FsError::Conflict(self.path(id))),
+            };
         }
-        let (size, name) = line.split_once(' ').ok_or(())?;
-        Ok(Self::File {
-            name: name.into(),
-            size: size.parse().map_err(drop)?,
-        })
+
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.clone(),
+            parent: dir,
+            size,
+            children: is_dir.then(BTreeMap::new),
+        });
+        self.link(id, dir);
+        Ok(id)
     }
-}
 
-impl core::str::FromStr for DirTarget {
-    type Err = ();
-    fn from_str(target: &str) -> Result<Self, ()> {
-        Ok(match target {
-            "/" => Self::Root,
-            ".." => DirTarget::Up,
-            name => DirTarget::Named { name: name.into() },
-        })
+    // put a node (with its size) into a directory
+    fn link(&mut self, id: NodeId, dir: NodeId) {
+        let name = self.nodes[id].name.clone();
+        self.nodes[id].parent = dir;
+        self.nodes[dir].children.as_mut().unwrap().insert(name, id);
+        let size = self.nodes[id].size;
+        self.each_dir_up(dir, |node| node.size += size);
     }
-}
 
-impl core::str::FromStr for Command {
-    type Err = ();
-    fn from_str(line: &str) -> Result<Self, ()> {
-        let (_, cmd_str) = line.split_once("$ ").ok_or(())?;
+    fn unlink(&mut self, id: NodeId) {
+        let parent = self.nodes[id].parent;
+        let name = self.nodes[id].name.clone();
+        self.nodes[parent].children.as_mut().unwrap().remove(&name);
+        let size = self.nodes[id].size;
+        self.each_dir_up(parent, |node| node.size -= size);
+    }
 
-        if let Some((_, target)) = cmd_str.split_once("cd ") {
-            Ok(Self::ChangeDir {
-                to: target.parse().unwrap(),
-            })
-        } else if cmd_str == "ls" {
-            Ok(Command::List)
-        } else {
-            Err(())
+    // a directory and everything above it
+    fn each_dir_up(&mut self, mut dir: NodeId, mut f: impl FnMut(&mut Node)) {
+        loop {
+            f(&mut self.nodes[dir]);
+            if dir == ROOT {
+                break;
+            }
+            dir = self.nodes[dir].parent;
         }
     }
-}
 
-fn rebuild_fs(input: &str) -> Inode {
-    let mut lines = input.trim().lines().peekable();
-    let mut fs: Inode = Inode::Dir {
-        name: "/".into(),
-        size: 0,
-        inodes: Vec::new(),
-    };
-
-    // track pwd as a vec of directory names
-    // empty vec == at the root
-    let mut pwd: Vec<String> = Vec::new();
-
-    'all: while let Some(cmd_line) = lines.next() {
-        // expect a command
-        match cmd_line.parse().unwrap() {
-            Command::List => loop {
-                let Some(next_line) = lines.peek() else {
-                    break 'all;
-                };
-                if next_line.starts_with("$ ") {
-                    break;
-                }
+    /// Like `rm -r`.
+    fn rm(&mut self, path: &str) -> Result<(), FsError> {
+        match self.lookup(path)? {
+            ROOT => Err(FsError::Root),
+            id => {
+                self.unlink(id);
+                Ok(())
+            }
+        }
+    }
 
-                // read inode lines and add to pwd
-                let inode: Inode = lines.next().unwrap().parse().unwrap();
-                let dir = fs.cd(&pwd).unwrap();
-                dir.push_inode(inode);
-            },
-            Command::ChangeDir { to } => match to {
-                DirTarget::Root => pwd.clear(),
-                DirTarget::Named { name } => pwd.push(name),
-                DirTarget::Up => drop(pwd.pop()),
-            },
+    /// Move and/or rename to `to`, which mustn't exist yet.
+    fn mv(&mut self, from: &str, to: &str) -> Result<(), FsError> {
+        let id = self.lookup(from)?;
+        let (dir, name) = match to.rsplit_once('/') {
+            Some((dir, name)) if !name.is_empty() && name != "." && name != ".." => (dir, name),
+            _ => return Err(FsError::NotFound(to.into())),
+        };
+        let dir = self.lookup(if dir.is_empty() { "/" } else { dir })?;
+        if id == ROOT {
+            return Err(FsError::Root);
+        }
+        if self.dir(dir)?.contains_key(name) {
+            return Err(FsError::AlreadyExists(to.into()));
         }
+        let mut up = dir;
+        while up != ROOT {
+            if up == id {
+                return Err(FsError::IntoItself(from.into()));
+            }
+            up = self.nodes[up].parent;
+        }
+
+        self.unlink(id);
+        self.nodes[id].name = name.into();
+        self.link(id, dir);
+        Ok(())
     }
 
-    // fill in directory sizes
-    fs.compute_size();
+    // everything under the root, parents before children
+    fn walk(&self) -> Vec<NodeId> {
+        let mut order = Vec::new();
+        let mut stack = vec![ROOT];
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            if let Some(children) = &self.nodes[id].children {
+                stack.extend(children.values().rev());
+            }
+        }
+        order
+    }
 
-    fs
-}
+    /// Every directory with its total size, like `du`.
+    fn du(&self) -> Vec<(String, usize)> {
+        self.walk()
+            .into_iter()
+            .filter(|&id| self.nodes[id].children.is_some())
+            .map(|id| (self.path(id), self.nodes[id].size))
+            .collect()
+    }
 
-// scan a dir, and return the sum of each dir size <= 100_000
-fn dfs_part1(dir: &Inode) -> usize {
-    let Inode::Dir { size, inodes, .. } = dir else {
-        // files don't get counted
-        return 0;
-    };
+    fn find(&self, predicate: impl Fn(&Entry) -> bool) -> Vec<Entry<'_>> {
+        self.walk()
+            .into_iter()
+            .map(|id| Entry {
+                path: self.path(id),
+                name: &self.nodes[id].name,
+                size: self.nodes[id].size,
+                is_dir: self.nodes[id].children.is_some(),
+            })
+            .filter(|e| predicate(e))
+            .collect()
+    }
 
-    // scan each inode, collecting to a sum
-    let mut sum = inodes.iter().map(dfs_part1).sum();
-    if *size <= 100_000 {
-        sum += size;
+    fn free_space(&self) -> usize {
+        FILESYSTEM_SIZE.saturating_sub(self.size("/").unwrap())
     }
+}
 
-    sum
+/// Like the puzzle's own drawing, with directory totals too.
+impl fmt::Display for Vfs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn draw(fs: &Vfs, f: &mut fmt::Formatter<'_>, id: NodeId, depth: usize) -> fmt::Result {
+            let node = &fs.nodes[id];
+            let name = if id == ROOT { "/" } else { &node.name };
+            write!(f, "{:indent$}- {name} ", "", indent = depth * 2)?;
+            match &node.children {
+                None => write!(f, "(file, size={})", node.size),
+                Some(children) => {
+                    write!(f, "(dir, size={})", node.size)?;
+                    for &child in children.values() {
+                        writeln!(f)?;
+                        draw(fs, f, child, depth + 1)?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+        draw(self, f, ROOT, 0)
+    }
 }
 
-fn part1(input: &str) -> usize {
-    let fs = rebuild_fs(input);
-    dfs_part1(&fs)
+/// Replays a transcript a line at a time.
+struct Session {
+    fs: Vfs,
+    // none after a bad `cd` (other than above the root), until the next
+    // `cd /`
+    cwd: Option<NodeId>,
+    listing: bool,
 }
 
-// scan a dir, and return the sum of each dir size <= 100_000
-fn dfs_part2(dir: &Inode, size_goal: usize, best_fit: &mut usize) {
-    let Inode::Dir { size, inodes, .. } = dir else {
-        // files don't get counted
-        return;
-    };
+impl Session {
+    fn new() -> Self {
+        Session {
+            fs: Vfs::new(),
+            cwd: Some(ROOT),
+            listing: false,
+        }
+    }
+
+    fn line(&mut self, line: &str) -> Result<(), FsError> {
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        if !line.starts_with('$') {
+            return match (self.listing, self.cwd) {
+                (false, _) => Err(FsError::BadLine(line.into())),
+                // whatever's listed in an unknown directory can't be placed
+                (true, None) => Ok(()),
+                (true, Some(cwd)) => self.fs.add(cwd, line.parse()?).map(drop),
+            };
+        }
 
-    if *size < size_goal {
-        // if this dir is smaller than the goal, then this one, nor the
-        // children will free up enough space
-        return;
+        self.listing = false;
+        match line.parse()? {
+            Command::List => self.listing = true,
+            Command::ChangeDir { to: DirTarget::Root } => self.cwd = Some(ROOT),
+            Command::ChangeDir { to } => {
+                let Some(cwd) = self.cwd else {
+                    return Ok(());
+                };
+                // an error, but like a shell it leaves us in the root
+                if cwd == ROOT && matches!(to, DirTarget::Up) {
+                    return Err(FsError::AboveRoot);
+                }
+                self.cwd = None;
+                let next = match to {
+                    DirTarget::Up => self.fs.nodes[cwd].parent,
+                    DirTarget::Named { name } => self.fs.child(cwd, &name)?,
+                    DirTarget::Root => unreachable!(),
+                };
+                self.fs.dir(next)?;
+                self.cwd = Some(next);
+            }
+        }
+        Ok(())
     }
+}
 
-    for ii in inodes {
-        dfs_part2(ii, size_goal, best_fit);
+/// Something wrong with a transcript, and its line number from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Problem {
+    line: usize,
+    error: FsError,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
     }
+}
 
-    // apply this size if it's the best
-    *best_fit = (*best_fit).min(*size);
+/// Every problem with a transcript. After a bad `cd` it carries on from the
+/// next `cd /`, so one mistake isn't reported over and over.
+fn validate(input: &str) -> Vec<Problem> {
+    let mut session = Session::new();
+    input
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let error = session.line(line).err()?;
+            Some(Problem { line: i + 1, error })
+        })
+        .collect()
 }
 
-fn part2(input: &str) -> usize {
-    let fs = rebuild_fs(input);
-    let Inode::Dir { size: space_taken, .. } = fs else {
-        panic!("root is not a dir");
-    };
+// the total of every directory of at most 100_000
+fn part1(fs: &Vfs) -> usize {
+    fs.find(|e| e.is_dir && e.size <= 100_000)
+        .iter()
+        .map(|e| e.size)
+        .sum()
+}
 
-    let free_disk_space = FILESYSTEM_SIZE - space_taken;
-    if free_disk_space >= SPACE_NEEDED {
+// the smallest directory that frees up enough space
+fn part2(fs: &Vfs) -> usize {
+    let needed = SPACE_NEEDED.saturating_sub(fs.free_space());
+    if needed == 0 {
         return 0;
     }
-    let delete_at_least = SPACE_NEEDED - free_disk_space;
-
-    let mut best_fit = usize::MAX;
-    dfs_part2(&fs, delete_at_least, &mut best_fit);
-    best_fit
+    fs.du()
+        .into_iter()
+        .map(|(_, size)| size)
+        .filter(|&size| size >= needed)
+        .min()
+        .unwrap()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // work the sizes out from scratch
+    fn total(fs: &Vfs, id: NodeId) -> usize {
+        match &fs.nodes[id].children {
+            None => fs.nodes[id].size,
+            Some(children) => children.values().map(|&c| total(fs, c)).sum(),
+        }
+    }
+
+    fn consistent(fs: &Vfs) -> bool {
+        fs.walk().iter().all(|&id| fs.nodes[id].size == total(fs, id))
+    }
+
     #[test]
     fn tests() {
+        let fs = Vfs::from_transcript(TEST_INPUT).unwrap();
+        assert_eq!(95437, part1(&fs));
+        assert_eq!(24933642, part2(&fs));
+    }
+
+    #[test]
+    fn transcripts() {
+        // listing again changes nothing
+        let fs = Vfs::from_transcript(TEST_INPUT).unwrap();
+        let again = format!("{TEST_INPUT}\n$ cd /\n$ ls\ndir a\n14848514 b.txt\n$ cd a/e\n");
+        assert_eq!(Err(FsError::NotFound("/a/e".into())), Vfs::from_transcript(&again));
+        let again = format!("{TEST_INPUT}\n$ cd /\n$ ls\ndir a\n14848514 b.txt\n$ cd a\n$ ls\n584 e\n");
+        assert_eq!(Err(FsError::Conflict("/a/e".into())), Vfs::from_transcript(&again));
+        let again = format!("{TEST_INPUT}\n$ cd /\n$ ls\ndir a\n14848514 b.txt\n");
+        assert_eq!(Ok(fs), Vfs::from_transcript(&again));
+
+        assert_eq!(
+            Err(FsError::UnknownCommand("pwd".into())),
+            Vfs::from_transcript("$ pwd")
+        );
+        assert_eq!(
+            Err(FsError::BadLine("dir a".into())),
+            Vfs::from_transcript("$ cd /\ndir a")
+        );
+        assert_eq!(
+            Err(FsError::NotADir("/f".into())),
+            Vfs::from_transcript("$ ls\n1 f\n$ cd f")
+        );
+    }
+
+    #[test]
+    fn queries() {
+        let fs = Vfs::from_transcript(TEST_INPUT).unwrap();
+        assert_eq!(Ok(584), fs.size("/a/e/i"));
+        assert_eq!(Ok(584), fs.size("/d/../a/./e"));
+        assert_eq!(Err(FsError::NotFound("/a/x".into())), fs.size("/a/x"));
+        assert_eq!(Err(FsError::NotADir("/b.txt".into())), fs.size("/b.txt/x"));
+        assert_eq!(
+            vec![
+                ("/".to_string(), 48381165),
+                ("/a".to_string(), 94853),
+                ("/a/e".to_string(), 584),
+                ("/d".to_string(), 24933642)
+            ],
+            fs.du()
+        );
+        let logs: Vec<String> = fs
+            .find(|e| e.name.starts_with("d.") && e.size > 6_000_000)
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        assert_eq!(vec!["/d/d.log"], logs);
+    }
+
+    #[test]
+    fn changes() {
+        let mut fs = Vfs::from_transcript(TEST_INPUT).unwrap();
+        fs.mv("/a/e", "/d/e2").unwrap();
+        assert_eq!(Ok(94853 - 584), fs.size("/a"));
+        assert_eq!(Ok(24933642 + 584), fs.size("/d"));
+        assert_eq!(Ok(584), fs.size("/d/e2/i"));
+        fs.mv("/d/j", "/j").unwrap();
+        fs.rm("/a").unwrap();
+        assert_eq!(Err(FsError::NotFound("/a".into())), fs.size("/a/f"));
+        assert_eq!(Ok(48381165 - 94853 + 584), fs.size("/"));
+        assert!(consistent(&fs));
+
+        assert_eq!(Err(FsError::Root), fs.rm("/"));
+        assert_eq!(Err(FsError::IntoItself("/d".into())), fs.mv("/d", "/d/e2/d"));
+        assert_eq!(Err(FsError::AlreadyExists("/d/k".into())), fs.mv("/j", "/d/k"));
+        assert!(consistent(&fs));
+    }
+
+    #[test]
+    fn tree() {
+        let mut fs = Vfs::from_transcript(TEST_INPUT).unwrap();
+        fs.rm("/d").unwrap();
+        assert_eq!(
+            "- / (dir, size=23447523)
+  - a (dir, size=94853)
+    - e (dir, size=584)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)",
+            fs.to_string()
+        );
+    }
+
+    #[test]
+    fn recording() {
+        let fs = Vfs::from_transcript(TEST_INPUT).unwrap();
+        let transcript = fs.transcript();
+        assert_eq!(
+            "$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd /
+$ cd d
+$ ls
+5626152 d.ext
+8033020 d.log
+4060174 j
+7214296 k
+",
+            transcript
+        );
+        let replayed = Vfs::from_transcript(&transcript).unwrap();
+        assert_eq!(fs.to_string(), replayed.to_string());
+
+        // empty directories are listed but not visited, and it backs up
+        // when that's quicker than starting from the top
+        let mut fs = Vfs::new();
+        let mut dir = ROOT;
+        for name in ["a", "b", "c"] {
+            dir = fs.add(dir, Listing::Dir { name: name.into() }).unwrap();
+        }
+        let a = fs.lookup("/a").unwrap();
+        fs.add(a, "1 x".parse().unwrap()).unwrap();
+        fs.add(dir, "2 y".parse().unwrap()).unwrap();
+        let b = fs.lookup("/a/b").unwrap();
+        fs.add(b, "dir z".parse().unwrap()).unwrap();
+        fs.add(ROOT, "dir e".parse().unwrap()).unwrap();
+        let transcript = fs.transcript();
         assert_eq!(
-            95437,
-            part1(
-                "$ cd /
+            "$ ls\ndir a\ndir e\n$ cd a\n$ ls\ndir b\n1 x\n$ cd b\n$ ls\ndir c\ndir z\n$ cd c\n$ ls\n2 y\n",
+            transcript
+        );
+        let replayed = Vfs::from_transcript(&transcript).unwrap();
+        assert_eq!(fs.to_string(), replayed.to_string());
+    }
+
+    #[test]
+    fn validation() {
+        assert_eq!(Vec::<Problem>::new(), validate(TEST_INPUT));
+
+        let input = "$ cd /
+$ ls
+dir a
+5 f
+$ cd b
+$ ls
+1 g
+$ cd /
+$ cd ..
+$ ls
+6 f
+$ cd a
+ls
+$ cd a
+";
+        let problems: Vec<String> = validate(input).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            vec![
+                "line 5: /b: no such file or directory",
+                "line 9: can't cd above /",
+                "line 11: /f: listed differently before",
+                "line 13: unexpected line \"ls\"",
+                "line 14: /a/a: no such file or directory",
+            ],
+            problems
+        );
+        assert_eq!(
+            Err(FsError::NotFound("/b".into())),
+            Vfs::from_transcript(input)
+        );
+    }
+
+    #[test]
+    fn commands() {
+        let parse = |line: &str| line.parse::<Command>();
+        assert_eq!(Ok(Command::List), parse("$ ls"));
+        let to = DirTarget::Named { name: "x".into() };
+        assert_eq!(Ok(Command::ChangeDir { to }), parse("$ cd x"));
+        // commands only count at the start of the line
+        assert_eq!(Err(FsError::UnknownCommand("abcd x".into())), parse("$ abcd x"));
+        assert_eq!(Err(FsError::UnknownCommand("lscd x".into())), parse("$ lscd x"));
+        assert_eq!(Err(FsError::BadLine("foo$ ls".into())), parse("foo$ ls"));
+    }
+
+    const TEST_INPUT: &str = "$ cd /
 $ ls
 dir a
 14848514 b.txt
@@ -258,8 +779,5 @@ $ ls
 4060174 j
 8033020 d.log
 5626152 d.ext
-7214296 k"
-            )
-        );
-    }
+7214296 k";
 }
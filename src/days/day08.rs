@@ -1,168 +1,368 @@
+use crate::params;
+use crate::render::{heat, Image, Rgb};
+use std::fmt;
+use std::str::FromStr;
+
+/// `-p day08.edges=true` adds how many trees can be seen from each edge,
+/// `-p day08.tree=<x>,<y>` how far that tree can see each way, and
+/// `-p day08.min=<h>` the most scenic tree at least `h` tall.
 pub fn solve(input: &str) -> String {
-    let input = input.trim();
-    let p1 = part1(input);
-    let p2 = part2(input);
-    format!("{p1}, {p2}")
-}
+    let forest: Forest = input.trim().parse().expect("bad tree heights");
+    let p1 = part1(&forest);
+    let p2 = part2(&forest);
+    let mut out = format!("{p1}, {p2}");
 
-fn parse_grid(input: &str) -> Vec<Vec<i8>> {
-    input
-        .lines()
-        .map(|row| row.as_bytes().iter().map(|b| *b as i8).collect())
-        .collect()
+    if params::get::<bool>("day08.edges").unwrap_or(false) {
+        let counts: Vec<String> = Direction::ALL
+            .iter()
+            .map(|&edge| {
+                let seen = forest.visible_from(edge).iter().filter(|&&v| v).count();
+                format!("{edge} {seen}")
+            })
+            .collect();
+        out += &format!(", seen from {}", counts.join(" "));
+    }
+    if let Some(tree) = params::get::<String>("day08.tree") {
+        let (x, y) = tree
+            .split_once(',')
+            .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+            .expect("day08.tree needs <x>,<y>");
+        let views: Vec<String> = Direction::ALL
+            .iter()
+            .map(|&dir| format!("{dir} {}", forest.view(x, y, dir).distance))
+            .collect();
+        out += &format!(", tree {x},{y} sees {}", views.join(" "));
+    }
+    if let Some(min) = params::get::<u8>("day08.min") {
+        match forest.best_at_least(min) {
+            Some(((x, y), score)) => out += &format!(", best at least {min} tall: {x},{y} scores {score}"),
+            None => out += &format!(", no trees at least {min} tall"),
+        }
+    }
+    out
 }
 
-// from https://stackoverflow.com/questions/64498617/how-to-transpose-a-vector-of-vectors-in-rust
-fn transpose2<T>(v: Vec<Vec<T>>) -> Vec<Vec<T>> {
-    assert!(!v.is_empty());
-    let len = v[0].len();
-    let mut iters: Vec<_> = v.into_iter().map(|n| n.into_iter()).collect();
-    (0..len)
-        .map(|_| {
-            iters
-                .iter_mut()
-                .map(|n| n.next().unwrap())
-                .collect::<Vec<T>>()
-        })
-        .collect()
+/// Which way a tree is looking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
 }
 
-fn part1(input: &str) -> usize {
-    let trees = parse_grid(input);
-    let columns = trees.len();
-    let mut treemap = vec![false; trees.len() * trees[0].len()];
-
-    let mut seen = |x, y| {
-        treemap[x + columns * y] = true;
-    };
-
-    for (row_index, row) in trees.iter().enumerate() {
-        // look right
-        let mut curr_height = -1;
-        for (col_index, &tree) in row.iter().enumerate() {
-            if tree > curr_height {
-                curr_height = tree;
-                seen(col_index, row_index);
-            }
-        }
-        // look left
-        let mut curr_height = -1;
-        for (col_index, &tree) in row.iter().enumerate().rev() {
-            if tree > curr_height {
-                curr_height = tree;
-                seen(col_index, row_index);
-            }
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::Left,
+        Direction::Right,
+        Direction::Up,
+        Direction::Down,
+    ];
+
+    fn step(self) -> (isize, isize) {
+        match self {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
         }
     }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::Up => "up",
+            Direction::Down => "down",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What a tree sees looking one way.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct View {
+    /// Trees up to and including the first one at least as tall, or up to
+    /// the edge.
+    distance: u32,
+    /// Nothing is in the way, so it's visible from that edge.
+    edge: bool,
+}
+
+/// The visibility mask and scenic scores of a whole forest, indexed like its
+/// heights.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Survey {
+    visible: Vec<bool>,
+    scores: Vec<u64>,
+}
 
-    // look up & down
-    let trees = transpose2(trees);
+/// Tree heights, row-major with (0, 0) at the top left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Forest {
+    width: usize,
+    height: usize,
+    heights: Vec<u8>,
+}
+
+// rows of digits, all the same length
+impl FromStr for Forest {
+    type Err = ();
 
-    for (col_index, row) in trees.iter().enumerate() {
-        // look right
-        let mut curr_height = -1;
-        for (row_index, &tree) in row.iter().enumerate() {
-            if tree > curr_height {
-                curr_height = tree;
-                seen(col_index, row_index);
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut width = None;
+        let mut heights = Vec::new();
+        for row in s.lines().map(str::trim).filter(|r| !r.is_empty()) {
+            if *width.get_or_insert(row.len()) != row.len() {
+                return Err(());
             }
-        }
-        // look left
-        let mut curr_height = -1;
-        for (row_index, &tree) in row.iter().enumerate().rev() {
-            if tree > curr_height {
-                curr_height = tree;
-                seen(col_index, row_index);
+            for b in row.bytes() {
+                heights.push(b.is_ascii_digit().then(|| b - b'0').ok_or(())?);
             }
         }
+        let width = width.unwrap_or(0);
+        Ok(Forest {
+            width,
+            height: heights.len().checked_div(width).unwrap_or(0),
+            heights,
+        })
     }
-
-    treemap.iter().filter(|f| **f).count()
 }
 
-fn part2(input: &str) -> usize {
-    let trees = parse_grid(input);
-
-    let mut best = 0;
+impl Forest {
+    fn index(&self, x: usize, y: usize) -> usize {
+        x + self.width * y
+    }
 
-    for (col_index, row) in trees.iter().enumerate() {
-        if col_index == 0 || col_index == trees.len() - 1 {
-            continue;
+    /// Each line of trees as (first index, step, length), ordered so that
+    /// walking it the trees behind are the ones `dir` looks at.
+    fn lines(&self, dir: Direction) -> Vec<(usize, isize, usize)> {
+        let (w, h) = (self.width, self.height);
+        match dir {
+            Direction::Left => (0..h).map(|y| (self.index(0, y), 1, w)).collect(),
+            Direction::Right if w > 0 => (0..h).map(|y| (self.index(w - 1, y), -1, w)).collect(),
+            Direction::Up => (0..w).map(|x| (x, w as isize, h)).collect(),
+            Direction::Down if h > 0 => (0..w)
+                .map(|x| (self.index(x, h - 1), -(w as isize), h))
+                .collect(),
+            _ => Vec::new(),
         }
-        for (row_index, &tree) in row.iter().enumerate() {
-            if row_index == 0 || row_index == row.len() - 1 {
-                continue;
-            }
+    }
 
-            // look left
-            let mut left = 1;
-            for (num, ii) in (0..row_index).rev().enumerate() {
-                left = num + 1;
-                if row[ii] >= tree {
-                    break;
+    /// What every tree sees looking `dir`, indexed like `heights`.
+    ///
+    /// Walking each line keeps a stack of the trees that could still block
+    /// the view, tallest at the bottom. Shorter trees are popped as each new
+    /// tree arrives, leaving the one it stops at on top, so each tree is
+    /// pushed and popped once and the whole forest is O(width * height).
+    fn views(&self, dir: Direction) -> Vec<View> {
+        let mut views = vec![View::default(); self.heights.len()];
+        let mut stack: Vec<(usize, u8)> = Vec::new();
+        for (start, step, len) in self.lines(dir) {
+            stack.clear();
+            for i in 0..len {
+                let index = start.wrapping_add_signed(step * i as isize);
+                let height = self.heights[index];
+                while stack.last().is_some_and(|&(_, h)| h < height) {
+                    stack.pop();
                 }
+                views[index] = match stack.last() {
+                    Some(&(blocker, _)) => View {
+                        distance: (i - blocker) as u32,
+                        edge: false,
+                    },
+                    None => View {
+                        distance: i as u32,
+                        edge: true,
+                    },
+                };
+                stack.push((i, height));
             }
+        }
+        views
+    }
 
-            // look right
-            let mut right = 1;
-            for (num, ii) in ((row_index + 1)..row.len()).enumerate() {
-                right = num + 1;
-                if row[ii] >= tree {
-                    break;
-                }
+    /// What one tree sees looking `dir`, walking out from it.
+    fn view(&self, x: usize, y: usize, dir: Direction) -> View {
+        let height = self.heights[self.index(x, y)];
+        let (dx, dy) = dir.step();
+        let (mut x, mut y) = (x as isize, y as isize);
+        let mut distance = 0;
+        loop {
+            (x, y) = (x + dx, y + dy);
+            if x < 0 || y < 0 || x >= self.width as isize || y >= self.height as isize {
+                return View {
+                    distance,
+                    edge: true,
+                };
             }
-
-            // look up
-            let mut up = 1;
-            for (num, ii) in (0..col_index).rev().enumerate() {
-                up = num + 1;
-                if trees[ii][row_index] >= tree {
-                    break;
-                }
+            distance += 1;
+            if self.heights[self.index(x as usize, y as usize)] >= height {
+                return View {
+                    distance,
+                    edge: false,
+                };
             }
+        }
+    }
 
-            // look down
-            let mut down = 1;
-            for (num, ii) in ((col_index + 1)..trees.len()).enumerate() {
-                down = num + 1;
-                if trees[ii][row_index] >= tree {
-                    break;
-                }
+    /// Which trees can be seen from the `edge` side of the forest.
+    fn visible_from(&self, edge: Direction) -> Vec<bool> {
+        self.views(edge).iter().map(|v| v.edge).collect()
+    }
+
+    /// Which trees can be seen from outside the forest, and each tree's
+    /// viewing distances multiplied together.
+    fn survey(&self) -> Survey {
+        let mut visible = vec![false; self.heights.len()];
+        let mut scores = vec![1; self.heights.len()];
+        for dir in Direction::ALL {
+            for (i, view) in self.views(dir).into_iter().enumerate() {
+                visible[i] |= view.edge;
+                scores[i] *= view.distance as u64;
             }
+        }
+        Survey { visible, scores }
+    }
+
+    /// The most scenic tree at least `min` tall, and its score. Ties go to
+    /// the first in reading order.
+    fn best_at_least(&self, min: u8) -> Option<((usize, usize), u64)> {
+        let scores = self.survey().scores;
+        let best = (0..self.heights.len())
+            .filter(|&i| self.heights[i] >= min)
+            .rev()
+            .max_by_key(|&i| scores[i])?;
+        Some(((best % self.width, best / self.width), scores[best]))
+    }
+}
+
+fn part1(forest: &Forest) -> usize {
+    forest.survey().visible.iter().filter(|&&v| v).count()
+}
+
+fn part2(forest: &Forest) -> u64 {
+    forest.best_at_least(0).map_or(0, |(_, score)| score)
+}
+
+/// Tree heights as a heat map, next to the trees visible from outside in green.
+pub fn render(input: &str) -> Image {
+    let forest: Forest = input.trim().parse().expect("bad tree heights");
+    let (w, h) = (forest.width, forest.height);
 
-            let score = left * right * up * down;
-            best = best.max(score);
+    let mut heights = Image::new(w, h, Rgb::NAVY);
+    let mut mask = Image::new(w, h, Rgb::NAVY);
+    let visible = forest.survey().visible;
+    for y in 0..h {
+        for x in 0..w {
+            let i = forest.index(x, y);
+            heights.set(x, y, heat(forest.heights[i] as f64, 0.0, 9.0));
+            if visible[i] {
+                mask.set(x, y, Rgb::GREEN);
+            }
         }
     }
 
-    best
+    heights.beside(&mask, 2).scaled(4)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::prelude::*;
+
+    // a forest of random heights
+    fn generate(width: usize, height: usize, seed: u64) -> Forest {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let heights = (0..width * height).map(|_| rng.gen_range(0..10)).collect();
+        Forest {
+            width,
+            height,
+            heights,
+        }
+    }
 
     #[test]
     fn tests() {
+        let forest: Forest = TEST_INPUT.parse().unwrap();
+        assert_eq!(21, part1(&forest));
+        assert_eq!(8, part2(&forest));
+        assert_eq!(Err(()), "123\n45".parse::<Forest>());
+        assert_eq!(Err(()), "1a3".parse::<Forest>());
+    }
+
+    #[test]
+    fn rectangles() {
+        // a wide forest and a tall one, both 2 and 3 trees across
+        let wide: Forest = "30373\n25512".parse().unwrap();
+        assert_eq!((5, 2), (wide.width, wide.height));
+        assert_eq!(10, part1(&wide));
+        let tall: Forest = "32\n05\n35\n71\n32".parse().unwrap();
+        assert_eq!(10, part1(&tall));
+
+        for (width, height) in [(7, 3), (3, 7), (1, 5), (5, 1), (12, 9)] {
+            let forest = generate(width, height, (width * height) as u64);
+            for dir in Direction::ALL {
+                let views = forest.views(dir);
+                for y in 0..height {
+                    for x in 0..width {
+                        let i = forest.index(x, y);
+                        assert_eq!(forest.view(x, y, dir), views[i], "{x},{y} {dir:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stress() {
+        let forest = generate(5000, 3000, 8);
+        let Survey { visible, scores } = forest.survey();
+        assert!(visible.iter().filter(|&&v| v).count() >= 2 * (5000 + 3000) - 4);
+
+        // spot check against looking out from each tree
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..2000 {
+            let (x, y) = (rng.gen_range(0..5000), rng.gen_range(0..3000));
+            let views = Direction::ALL.map(|dir| forest.view(x, y, dir));
+            let score: u64 = views.iter().map(|v| v.distance as u64).product();
+            assert_eq!(score, scores[forest.index(x, y)]);
+            assert_eq!(views.iter().any(|v| v.edge), visible[forest.index(x, y)]);
+        }
+    }
+
+    #[test]
+    fn queries() {
+        let forest: Forest = TEST_INPUT.parse().unwrap();
+        let rows = |mask: Vec<bool>| -> Vec<String> {
+            mask.chunks(forest.width)
+                .map(|row| row.iter().map(|&v| if v { '#' } else { '.' }).collect())
+                .collect()
+        };
         assert_eq!(
-            21,
-            part1(
-                "30373
-25512
-65332
-33549
-35390"
-            )
+            vec!["#..#.", "##...", "#....", "#.#.#", "##.#."],
+            rows(forest.visible_from(Direction::Left))
         );
         assert_eq!(
-            8,
-            part2(
-                "30373
+            vec!["#####", "###.#", "##.##", "#.#.#", "#####"],
+            rows(forest.survey().visible)
+        );
+
+        // the middle 5 in the second row
+        let views = Direction::ALL.map(|dir| forest.view(2, 1, dir).distance);
+        assert_eq!([1, 2, 1, 2], views);
+        assert_eq!(Some(((2, 3), 8)), forest.best_at_least(0));
+        assert_eq!(Some(((2, 3), 8)), forest.best_at_least(5));
+        // the 6, 7 and 9 are all on the edge, so the first one wins
+        assert_eq!(Some(((3, 0), 0)), forest.best_at_least(6));
+        assert_eq!(None, forest.best_at_least(10));
+    }
+
+    const TEST_INPUT: &str = "30373
 25512
 65332
 33549
-35390"
-            )
-        );
-    }
+35390";
 }
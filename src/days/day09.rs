@@ -1,19 +1,60 @@
+use crate::params;
+use crate::viz::{Color, Disabled, Frame, Observer};
 use std::{
-    collections::HashSet,
+    collections::HashMap,
+    fmt,
     ops::{Add, Sub},
 };
 
+/// `-p day09.knots=<n>` and `-p day09.leash=<n>` add how many cells the tail
+/// of a rope like that visits (10 knots trailing by 1 if only one is given),
+/// or with `-p day09.knot=<k>` the `k`th knot, counting the head as 0.
+///
+/// For that rope, or the part 2 one, `-p day09.map=true` adds a map of how
+/// often the knot visited each cell, `-p day09.after=<n>` a snapshot of the
+/// whole rope after `n` moves, and `-p day09.path=true` every cell the tail
+/// passed through, in order.
 pub fn solve(input: &str) -> String {
     let input = input.trim();
     let p1 = part1(input);
     let p2 = part2(input);
-    format!("{p1}, {p2}")
+    let mut out = format!("{p1}, {p2}");
+
+    let knots = params::get::<usize>("day09.knots");
+    let leash = params::get::<i64>("day09.leash");
+    let knot = params::get::<usize>("day09.knot");
+    let (knots_or, leash_or) = (knots.unwrap_or(10), leash.unwrap_or(1));
+    assert!(knots_or > 0, "day09.knots needs to be at least 1, for the head");
+    assert!(leash_or > 0, "day09.leash needs to be at least 1");
+    let knot_or = knot.unwrap_or(knots_or - 1);
+    assert!(
+        knot_or < knots_or,
+        "day09.knot needs to be below {knots_or}, the number of knots"
+    );
+    let sim = || simulate(input, knots_or, leash_or, &mut Disabled);
+
+    if knots.is_some() || leash.is_some() || knot.is_some() {
+        out += &format!(
+            ", knot {knot_or} of {knots_or} (leash {leash_or}) visits {}",
+            sim().visited(knot_or)
+        );
+    }
+    if params::get::<bool>("day09.map").unwrap_or(false) {
+        out += &format!("\n{}", sim().trail(knot_or));
+    }
+    if let Some(after) = params::get::<usize>("day09.after") {
+        out += &format!("\n{}", simulate_until(input, knots_or, leash_or, after).snapshot());
+    }
+    if params::get::<bool>("day09.path").unwrap_or(false) {
+        out += &format!("\n{}", sim().export_path());
+    }
+    out
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 struct XY {
-    x: i16,
-    y: i16,
+    x: i64,
+    y: i64,
 }
 
 impl XY {
@@ -60,120 +101,262 @@ impl Sub for XY {
     }
 }
 
-#[derive(Debug, Default, Clone, Copy)]
-struct Rope {
-    head: XY,
-    tail: XY,
+/// A rope of knots where each knot follows the one before it, trailing at
+/// most `leash` cells behind (in any direction, diagonals included).
+#[derive(Debug, Clone)]
+struct RopeSim {
+    knots: Vec<XY>,
+    leash: i64,
+    // how many times each knot has arrived on each cell
+    visits: Vec<HashMap<XY, u32>>,
+    // every cell the tail has been on, in order
+    path: Vec<XY>,
 }
 
-impl Rope {
-    fn tail_moves(&self) -> bool {
-        let delta = self.head - self.tail;
-        let abs = delta.abs();
-        abs.x > 1 || abs.y > 1
+impl RopeSim {
+    fn new(knots: usize, leash: i64) -> Self {
+        assert!(knots > 0, "a rope needs a head");
+        assert!(leash > 0, "knots can't share a cell");
+        RopeSim {
+            knots: vec![XY::default(); knots],
+            leash,
+            visits: vec![HashMap::from([(XY::default(), 1)]); knots],
+            path: vec![XY::default()],
+        }
+    }
+
+    fn head(&self) -> XY {
+        self.knots[0]
     }
 
-    // take one step
-    fn move_tail(&mut self) {
-        let delta = self.head - self.tail;
-        self.tail = self.tail + delta.unit();
+    /// Move the head one cell, which can be diagonal, and let the rest of
+    /// the rope catch up.
+    fn step(&mut self, dir: XY) {
+        self.knots[0] = self.knots[0] + dir;
+        *self.visits[0].entry(self.knots[0]).or_default() += 1;
+        for i in 1..self.knots.len() {
+            let delta = self.knots[i - 1] - self.knots[i];
+            let abs = delta.abs();
+            if abs.x.max(abs.y) <= self.leash {
+                // and nothing behind it moves either
+                break;
+            }
+            self.knots[i] = self.knots[i] + delta.unit();
+            *self.visits[i].entry(self.knots[i]).or_default() += 1;
+        }
+        let tail = *self.knots.last().unwrap();
+        if self.path.last() != Some(&tail) {
+            self.path.push(tail);
+        }
+    }
+
+    fn run<O: Observer>(&mut self, moves: &[Move], observer: &mut O) {
+        for mov in moves {
+            for _ in 0..mov.count {
+                self.step(mov.dir);
+                if O::ENABLED {
+                    observer.frame(draw(self));
+                }
+            }
+        }
+    }
+
+    /// How many cells a knot has been on, counting the head as knot 0.
+    fn visited(&self, knot: usize) -> usize {
+        self.visits[knot].len()
+    }
+
+    fn trail(&self, knot: usize) -> TrailMap {
+        let visits = &self.visits[knot];
+        let (min, max) = bounds(visits.keys().copied());
+        let (width, height) = ((max.x - min.x + 1) as usize, (max.y - min.y + 1) as usize);
+        let mut counts = vec![0; width * height];
+        for (at, &count) in visits {
+            counts[(at.x - min.x) as usize + width * (max.y - at.y) as usize] = count;
+        }
+        TrailMap { width, counts }
+    }
+
+    /// Where the knots are, drawn like the puzzle does: `H` for the head,
+    /// then each knot's number, and `s` for the start, cropped to fit them.
+    /// Knots nearer the head are drawn on top.
+    fn snapshot(&self) -> String {
+        let (min, max) = bounds(self.knots.iter().copied().chain([XY::default()]));
+        let mut rows = Vec::new();
+        for y in (min.y..=max.y).rev() {
+            let row: String = (min.x..=max.x)
+                .map(|x| {
+                    let at = XY { x, y };
+                    match self.knots.iter().position(|&k| k == at) {
+                        Some(0) => 'H',
+                        Some(knot) => char::from_digit(knot as u32, 36).unwrap_or('*'),
+                        None if at == XY::default() => 's',
+                        None => '.',
+                    }
+                })
+                .collect();
+            rows.push(row);
+        }
+        rows.join("\n")
+    }
+
+    /// The tail's path as `x,y` lines.
+    fn export_path(&self) -> String {
+        let lines: Vec<String> = self.path.iter().map(|at| format!("{},{}", at.x, at.y)).collect();
+        lines.join("\n")
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Direction {
-    Right,
-    Up,
+// the corners of the box around some cells, smallest x and y first
+fn bounds(cells: impl Iterator<Item = XY>) -> (XY, XY) {
+    cells.fold((XY::default(), XY::default()), |(min, max), at| {
+        (
+            XY { x: min.x.min(at.x), y: min.y.min(at.y) },
+            XY { x: max.x.max(at.x), y: max.y.max(at.y) },
+        )
+    })
+}
+
+/// How many times a knot arrived on each cell, cropped to where it went and
+/// with y growing up. Counts over 9 are shown as `+`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrailMap {
+    width: usize,
+    // rows from the top
+    counts: Vec<u32>,
+}
+
+impl fmt::Display for TrailMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.counts.chunks(self.width).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for &count in row {
+                let ch = match count {
+                    0 => '.',
+                    1..=9 => char::from_digit(count, 10).unwrap(),
+                    _ => '+',
+                };
+                write!(f, "{ch}")?;
+            }
+        }
+        Ok(())
+    }
 }
-use Direction::*;
 
-#[derive(Debug, Clone, Copy)]
+/// Move the head `count` cells one way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Move {
-    direction: Direction,
-    len: i16,
+    dir: XY,
+    count: u32,
 }
 
+// R, L, U or D, or a diagonal like UR, then how far
 impl std::str::FromStr for Move {
     type Err = ();
 
-    #[rustfmt::skip]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (dir, len) = s.split_once(' ').unwrap();
-        let len = len.parse().unwrap();
-        Ok(match dir {
-            "R" => Self { direction: Direction::Right, len },
-            "L" => Self { direction: Direction::Right, len: -len },
-            "U" => Self { direction: Direction::Up, len },
-            "D" => Self { direction: Direction::Up, len: -len },
-            _ => panic!(),
+        let (dir, count) = s.trim().split_once(' ').ok_or(())?;
+        let mut step = XY::default();
+        for c in dir.chars() {
+            let (x, y) = match c {
+                'R' => (1, 0),
+                'L' => (-1, 0),
+                'U' => (0, 1),
+                'D' => (0, -1),
+                _ => return Err(()),
+            };
+            // one of each axis at most
+            if (x != 0 && step.x != 0) || (y != 0 && step.y != 0) {
+                return Err(());
+            }
+            step = step + XY { x, y };
+        }
+        if step == XY::default() {
+            return Err(());
+        }
+        Ok(Move {
+            dir: step,
+            count: count.parse().map_err(drop)?,
         })
     }
 }
 
-impl From<&Move> for XY {
-    fn from(mov: &Move) -> Self {
-        match mov.direction {
-            Right => XY { x: mov.len, y: 0 },
-            Up => XY { x: 0, y: mov.len },
-        }
-    }
+fn parse_moves(input: &str) -> Vec<Move> {
+    input
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.parse().expect("bad move"))
+        .collect()
 }
 
-fn part1(input: &str) -> usize {
-    let moves: Vec<Move> = input.lines().map(|l| l.parse().unwrap()).collect();
-    let mut rope = Rope {
-        head: XY { x: 0, y: 0 },
-        tail: XY { x: 0, y: 0 },
-    };
-    let mut visited = HashSet::from([rope.tail]);
-
-    for mov in &moves {
-        // head can actually move all at once
-        rope.head = rope.head + mov.into();
+fn simulate(input: &str, knots: usize, leash: i64, observer: &mut impl Observer) -> RopeSim {
+    let mut sim = RopeSim::new(knots, leash);
+    sim.run(&parse_moves(input), observer);
+    sim
+}
 
-        // tail may have multiple steps though
-        while rope.tail_moves() {
-            let delta = rope.head - rope.tail;
-            rope.tail = rope.tail + delta.unit();
-            visited.insert(rope.tail);
-        }
-    }
+/// The rope after only the first `count` moves.
+fn simulate_until(input: &str, knots: usize, leash: i64, count: usize) -> RopeSim {
+    let moves = parse_moves(input);
+    let mut sim = RopeSim::new(knots, leash);
+    sim.run(&moves[..count.min(moves.len())], &mut Disabled);
+    sim
+}
 
-    visited.len()
+fn part1(input: &str) -> usize {
+    simulate(input, 2, 1, &mut Disabled).visited(1)
 }
 
 fn part2(input: &str) -> usize {
-    let moves: Vec<Move> = input.lines().map(|l| l.parse().unwrap()).collect();
-    let mut ropes = [Rope::default(); 9];
-    let mut visited = HashSet::from([XY::default()]);
-
-    // in this rope physics, the head must move one step at a time, and the
-    // tails only ever move once as a result
-    for mov in &moves {
-        let first_delta: XY = mov.into();
-        for _ in 0..mov.len.abs() {
-            let mut last_tail = ropes[0].head + first_delta.unit();
-
-            // move the first 8 knots
-            for rope in &mut ropes[..8] {
-                rope.head = last_tail;
-                if rope.tail_moves() {
-                    rope.move_tail();
-                }
-                last_tail = rope.tail;
-            }
+    simulate(input, 10, 1, &mut Disabled).visited(9)
+}
 
-            // move the last knot
-            let last = ropes.last_mut().unwrap();
-            last.head = last_tail;
-            if last.tail_moves() {
-                last.move_tail();
-                visited.insert(last.tail);
-            }
+/// Show the 10 knot rope from part 2, one frame per step of the head.
+pub fn visualize(input: &str, observer: &mut impl Observer) {
+    simulate(input, 10, 1, observer);
+}
+
+// size of the window that follows the head around
+const VIEW: XY = XY { x: 61, y: 31 };
+
+fn draw(sim: &RopeSim) -> Frame {
+    let head = sim.head();
+    let corner = head - XY {
+        x: VIEW.x / 2,
+        y: VIEW.y / 2,
+    };
+
+    let mut frame = Frame::new(VIEW.x as usize, VIEW.y as usize);
+    let mut set = |at: XY, ch, color| {
+        let rel = at - corner;
+        if (0..VIEW.x).contains(&rel.x) && (0..VIEW.y).contains(&rel.y) {
+            // y grows up, frames grow down
+            frame.set(rel.x as usize, (VIEW.y - 1 - rel.y) as usize, ch, color);
         }
+    };
+
+    let tail = sim.knots.len() - 1;
+    for &at in sim.visits[tail].keys() {
+        set(at, '#', Color::Gray);
     }
+    set(XY::default(), 's', Color::Red);
 
-    visited.len()
+    // draw back to front, so knots closer to the head end up on top
+    for (knot, &at) in sim.knots.iter().enumerate().skip(1).rev() {
+        let ch = char::from_digit(knot as u32, 36).unwrap_or('*');
+        set(at, ch, Color::Cyan);
+    }
+    set(head, 'H', Color::Yellow);
+
+    frame.set_caption(format!(
+        "head at {}, {}; tail visited {}",
+        head.x,
+        head.y,
+        sim.visited(tail)
+    ));
+    frame
 }
 
 #[cfg(test)]
@@ -181,11 +364,83 @@ mod tests {
     use super::*;
 
     #[test]
-    fn tests() {
+    fn frames() {
+        let mut frames = Vec::new();
+        visualize("R 5\nU 8", &mut frames);
+        assert_eq!(13, frames.len());
+
+        // the head is always in the middle of the view
+        let last = frames.last().unwrap();
+        let middle = last.get(30, 15).unwrap();
+        assert_eq!('H', middle.ch);
+        assert_eq!("head at 5, 8; tail visited 1", last.caption());
+    }
+
+    #[test]
+    fn ropes() {
+        // diagonal moves
+        assert_eq!(Ok(Move { dir: XY { x: 1, y: 1 }, count: 3 }), "UR 3".parse());
+        assert_eq!(Ok(Move { dir: XY { x: -1, y: -1 }, count: 2 }), "LD 2".parse());
+        assert_eq!(Err(()), "UD 3".parse::<Move>());
+        assert_eq!(Err(()), "X 3".parse::<Move>());
+        assert_eq!(Err(()), "R -3".parse::<Move>());
+        assert_eq!(3, simulate("UR 3", 2, 1, &mut Disabled).visited(1));
+
+        // a longer leash
+        let sim = simulate("R 4\nU 1", 2, 2, &mut Disabled);
+        assert_eq!(XY { x: 2, y: 0 }, sim.knots[1]);
+        assert_eq!(3, sim.visited(1));
+
+        // any knot of a long rope goes where the tail of a shorter one would
+        let long = simulate(LARGER_INPUT, 10, 1, &mut Disabled);
+        for knot in 1..10 {
+            let short = simulate(LARGER_INPUT, knot + 1, 1, &mut Disabled);
+            assert_eq!(short.visits[knot], long.visits[knot]);
+        }
+        assert_eq!(88, long.visited(1));
+
+        // far enough that 16 bits would overflow
+        let far = simulate("R 40000\nU 40000", 10, 1, &mut Disabled);
+        assert_eq!(XY { x: 40000, y: 40000 }, far.head());
+        assert_eq!(40000 + 40000 - 2 * 8 - 1, far.visited(9));
+    }
+
+    #[test]
+    fn snapshots() {
+        let sim = simulate_until(TEST_INPUT, 10, 1, 1);
+        assert_eq!("4321H", sim.snapshot());
+        let sim = simulate_until(TEST_INPUT, 10, 1, 2);
+        assert_eq!("....H\n....1\n..432\n.5...\n6....", sim.snapshot());
+        // before anything moves, the head covers everything
+        assert_eq!("H", simulate_until(TEST_INPUT, 10, 1, 0).snapshot());
+    }
+
+    #[test]
+    fn trails() {
+        let sim = simulate(TEST_INPUT, 2, 1, &mut Disabled);
+        let map = sim.trail(1).to_string();
         assert_eq!(
-            13,
-            part1(
-                "R 4
+            "..##.\n...##\n.####\n....#\n####.",
+            map.replace(|c: char| c.is_ascii_digit() || c == '+', "#")
+        );
+        // the tail comes back to (4, 3) on its way round
+        assert_eq!("..11.\n...12\n.1111\n....1\n1111.", map);
+
+        let path = sim.export_path();
+        assert!(path.starts_with("0,0\n1,0\n2,0\n3,0\n4,1\n"));
+        assert_eq!(sim.path.len(), path.lines().count());
+        assert!(sim.path.iter().all(|at| sim.visits[1].contains_key(at)));
+        let arrivals: u32 = sim.visits[1].values().sum();
+        assert_eq!(sim.path.len() as u32, arrivals);
+    }
+
+    #[test]
+    fn tests() {
+        assert_eq!(13, part1(TEST_INPUT));
+        assert_eq!(36, part2(LARGER_INPUT));
+    }
+
+    const TEST_INPUT: &str = "R 4
 U 4
 L 3
 D 1
@@ -193,21 +448,14 @@ R 4
 D 1
 L 5
 R 2
-"
-            )
-        );
-        assert_eq!(
-            36,
-            part2(
-                "R 5
+";
+
+    const LARGER_INPUT: &str = "R 5
 U 8
 L 8
 D 3
 R 17
 D 10
 L 25
-U 20"
-            )
-        );
-    }
+U 20";
 }
@@ -1,16 +1,25 @@
+use crate::params;
+use std::collections::HashMap;
 use std::default::Default;
+use std::fmt;
 use std::str::FromStr;
 
+/// `-p day10.costs=<op>:<cycles>,...` changes how long instructions take,
+/// e.g. `add:1,mul:3`.
 pub fn solve(input: &str) -> String {
     let input = input.trim();
-    let p1 = part1(input);
-    let p2 = part2(input);
+    let instructions = assemble(input).unwrap_or_else(|e| panic!("{e}"));
+    let mut costs = CycleCosts::default();
+    if let Some(table) = params::get::<String>("day10.costs") {
+        costs = costs.with(&table).expect("day10.costs wants <op>:<cycles>,...");
+    }
+    let p1 = part1(&instructions, costs);
+    let p2 = part2(&instructions, costs);
     format!("{p1}, \n{p2}")
 }
 
-fn part1(input: &str) -> isize {
-    let instructions = parse_instructions(input).unwrap();
-    let mut device = Device::with_instructions(instructions);
+fn part1(instructions: &[Instruction], costs: CycleCosts) -> i64 {
+    let mut device = Device::with_instructions(instructions.to_vec(), costs);
     let mut sum = 0;
 
     // I think this has to start at one because the ticks are talked about as
@@ -21,16 +30,16 @@ fn part1(input: &str) -> isize {
             device.tick();
             tick += 1;
         }
-        sum += device.cpu.x as isize * tick as isize;
+        sum += device.cpu.get(Reg::X) * tick;
     }
 
     sum
 }
 
-fn part2(input: &str) -> String {
-    let instructions = parse_instructions(input).unwrap();
-    let mut device = Device::with_instructions(instructions);
-    device.run_until_done();
+fn part2(instructions: &[Instruction], costs: CycleCosts) -> String {
+    let mut device = Device::with_instructions(instructions.to_vec(), costs);
+    // the screen is 6 rows of 40, and jumps can loop forever
+    device.run(6 * 40);
 
     device.crt.buffer
 }
@@ -47,103 +56,354 @@ impl Device {
         self.cpu.tick();
     }
 
-    fn with_instructions(instructions: Vec<Instruction>) -> Self {
+    fn with_instructions(instructions: Vec<Instruction>, costs: CycleCosts) -> Self {
         let mut device = Self::default();
-        device.cpu = CpuState::with_instructions(instructions);
+        device.cpu = CpuState::with_instructions(instructions, costs);
         device
     }
 
-    fn run_until_done(&mut self) {
-        while self.cpu.instructions.len() > self.cpu.pc {
+    /// Run until the program halts, or for at most `cycles`.
+    fn run(&mut self, cycles: usize) {
+        for _ in 0..cycles {
+            if self.cpu.done() {
+                break;
+            }
             self.tick();
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reg {
+    X,
+    Y,
+    Z,
+    W,
+}
+
+impl Reg {
+    fn parse(s: &str) -> Result<Reg, AsmError> {
+        match s {
+            "x" => Ok(Reg::X),
+            "y" => Ok(Reg::Y),
+            "z" => Ok(Reg::Z),
+            "w" => Ok(Reg::W),
+            _ => Err(AsmError::BadRegister(s.into())),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct CpuState {
     // ticks since last complete instruction
     ticks: usize,
+    // ticks since the start
+    cycles: usize,
     // program counter
     pc: usize,
-    // X register
-    x: i16,
+    // X, Y, Z and W
+    registers: [i64; 4],
     // instructions
     instructions: Vec<Instruction>,
+    costs: CycleCosts,
 }
 
 impl Default for CpuState {
     fn default() -> Self {
         Self {
             ticks: 0,
-            x: 1,
+            cycles: 0,
+            registers: [1, 0, 0, 0],
             pc: 0,
             instructions: Vec::new(),
+            costs: CycleCosts::default(),
         }
     }
 }
 
 impl CpuState {
+    /// Spend a cycle on the current instruction, or nothing once the program
+    /// has halted.
     fn tick(&mut self) {
-        let instruction = &self.instructions[self.pc];
+        let Some(&instruction) = self.instructions.get(self.pc) else {
+            return;
+        };
         self.ticks += 1;
+        self.cycles += 1;
 
-        if instruction.ticks() > self.ticks {
+        if self.costs.of(instruction.op()) > self.ticks {
             return;
         }
 
         // enough ticks have passed, update the cpu state
 
+        let value = |operand| match operand {
+            Operand::Immediate(value) => value,
+            Operand::Reg(reg) => self.get(reg),
+        };
+        let mut next = self.pc + 1;
         match instruction {
             Instruction::Nop => {}
-            Instruction::AddX { immediate } => {
-                self.x += *immediate;
+            // registers wrap round rather than overflow
+            Instruction::Add(reg, operand) => self.set(reg, self.get(reg).wrapping_add(value(operand))),
+            Instruction::Sub(reg, operand) => self.set(reg, self.get(reg).wrapping_sub(value(operand))),
+            Instruction::Mul(reg, operand) => self.set(reg, self.get(reg).wrapping_mul(value(operand))),
+            Instruction::Jump { reg, zero, offset } => {
+                if (self.get(reg) == 0) == zero {
+                    // jumping off either end stops the program
+                    next = self
+                        .pc
+                        .checked_add_signed(offset)
+                        .map_or(self.instructions.len(), |pc| pc.min(self.instructions.len()));
+                }
             }
         }
 
-        self.pc += 1;
+        self.pc = next;
         self.ticks = 0;
     }
 
-    fn with_instructions(instructions: Vec<Instruction>) -> Self {
-        let mut s = Self::default();
-        s.instructions = instructions;
-        s
+    fn with_instructions(instructions: Vec<Instruction>, costs: CycleCosts) -> Self {
+        Self {
+            instructions,
+            costs,
+            ..Self::default()
+        }
+    }
+
+    fn get(&self, reg: Reg) -> i64 {
+        self.registers[reg as usize]
+    }
+
+    fn set(&mut self, reg: Reg, value: i64) {
+        self.registers[reg as usize] = value;
+    }
+
+    fn done(&self) -> bool {
+        self.pc >= self.instructions.len()
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Immediate(i64),
+    Reg(Reg),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Instruction {
     Nop,
-    AddX { immediate: i16 },
+    Add(Reg, Operand),
+    Sub(Reg, Operand),
+    Mul(Reg, Operand),
+    /// Move `offset` instructions from here if the register is zero (or
+    /// isn't, when `zero` is false).
+    Jump {
+        reg: Reg,
+        zero: bool,
+        offset: isize,
+    },
 }
 
-impl FromStr for Instruction {
-    type Err = ();
+/// What kind of instruction, for looking up how long it takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Nop,
+    Add,
+    Sub,
+    Mul,
+    Jump,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s == "noop" {
-            Ok(Self::Nop)
-        } else if let Some((_, val)) = s.split_once("addx ") {
-            let immediate = val.parse().map_err(drop)?;
-            Ok(Self::AddX { immediate })
-        } else {
-            Err(())
+impl Op {
+    const ALL: [Op; 5] = [Op::Nop, Op::Add, Op::Sub, Op::Mul, Op::Jump];
+
+    fn name(self) -> &'static str {
+        match self {
+            Op::Nop => "noop",
+            Op::Add => "add",
+            Op::Sub => "sub",
+            Op::Mul => "mul",
+            Op::Jump => "jump",
         }
     }
 }
 
+/// How many cycles each kind of instruction takes, indexed by `Op`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CycleCosts([usize; 5]);
+
+impl Default for CycleCosts {
+    // noop and addx as in the puzzle
+    fn default() -> Self {
+        CycleCosts([1, 2, 2, 4, 2])
+    }
+}
+
+impl CycleCosts {
+    fn of(&self, op: Op) -> usize {
+        self.0[op as usize]
+    }
+
+    /// Change some costs, from a list like `add:1,mul:3`. Every instruction
+    /// takes at least one cycle.
+    fn with(mut self, table: &str) -> Option<Self> {
+        for entry in table.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, cycles) = entry.split_once(':')?;
+            let op = Op::ALL.into_iter().find(|op| op.name() == name.trim())?;
+            self.0[op as usize] = cycles.trim().parse().ok().filter(|&c| c > 0)?;
+        }
+        Some(self)
+    }
+}
+
 impl Instruction {
-    const fn ticks(&self) -> usize {
+    fn op(&self) -> Op {
+        match self {
+            Instruction::Nop => Op::Nop,
+            Instruction::Add(..) => Op::Add,
+            Instruction::Sub(..) => Op::Sub,
+            Instruction::Mul(..) => Op::Mul,
+            Instruction::Jump { .. } => Op::Jump,
+        }
+    }
+
+    /// Parse one instruction, at `index` in a program with these labels.
+    fn parse(s: &str, index: usize, labels: &HashMap<&str, usize>) -> Result<Self, AsmError> {
+        let mut words = s.split_whitespace();
+        let mnemonic = words.next().unwrap_or_default();
+        let operands: Vec<&str> = words.collect();
+        let wrong_operands = || AsmError::WrongOperands(mnemonic.into());
+
+        if mnemonic == "noop" {
+            return match operands[..] {
+                [] => Ok(Instruction::Nop),
+                _ => Err(wrong_operands()),
+            };
+        }
+
+        let Some((op, reg)) = ["add", "sub", "mul", "jnz", "jz"]
+            .iter()
+            .find_map(|op| Some((*op, mnemonic.strip_prefix(op)?)))
+        else {
+            return Err(AsmError::UnknownInstruction(mnemonic.into()));
+        };
+        let reg = Reg::parse(reg)?;
+        let [operand] = operands[..] else {
+            return Err(wrong_operands());
+        };
+
+        if let Some(zero) = [("jz", true), ("jnz", false)]
+            .iter()
+            .find_map(|&(j, zero)| (op == j).then_some(zero))
+        {
+            let offset = match operand.parse::<isize>() {
+                Ok(offset) => offset,
+                Err(_) if is_label(operand) => match labels.get(operand) {
+                    Some(&target) => target as isize - index as isize,
+                    None => return Err(AsmError::UnknownLabel(operand.into())),
+                },
+                Err(_) => return Err(AsmError::BadLabel(operand.into())),
+            };
+            return Ok(Instruction::Jump { reg, zero, offset });
+        }
+
+        let operand = match operand.parse() {
+            Ok(value) => Operand::Immediate(value),
+            Err(_) => Operand::Reg(Reg::parse(operand).map_err(|_| AsmError::BadOperand(operand.into()))?),
+        };
+        Ok(match op {
+            "add" => Instruction::Add(reg, operand),
+            "sub" => Instruction::Sub(reg, operand),
+            _ => Instruction::Mul(reg, operand),
+        })
+    }
+}
+
+// a single instruction, so jumps can only be by a number of instructions
+impl FromStr for Instruction {
+    type Err = AsmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Instruction::parse(s, 0, &HashMap::new())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AsmError {
+    UnknownInstruction(String),
+    BadRegister(String),
+    BadOperand(String),
+    WrongOperands(String),
+    /// Not something that could be a label name.
+    BadLabel(String),
+    UnknownLabel(String),
+    DuplicateLabel(String),
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Instruction::Nop => 1,
-            Instruction::AddX { .. } => 2,
+            AsmError::UnknownInstruction(s) => write!(f, "unknown instruction {s:?}"),
+            AsmError::BadRegister(s) => write!(f, "no register {s:?}"),
+            AsmError::BadOperand(s) => write!(f, "bad operand {s:?}"),
+            AsmError::WrongOperands(s) => write!(f, "wrong number of operands for {s}"),
+            AsmError::BadLabel(s) => write!(f, "{s:?} can't be a label"),
+            AsmError::UnknownLabel(s) => write!(f, "no label {s:?}"),
+            AsmError::DuplicateLabel(s) => write!(f, "label {s:?} is already defined"),
         }
     }
 }
 
-fn parse_instructions(input: &str) -> Result<Vec<Instruction>, ()> {
-    input.lines().map(|l| l.parse::<Instruction>()).collect()
+/// An assembler error, and its line number from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LineError {
+    line: usize,
+    error: AsmError,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+fn is_label(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Assemble a program, one instruction per line. A line can also start with
+/// `label:` for jumps to use, and anything after `;` is a comment.
+fn assemble(source: &str) -> Result<Vec<Instruction>, LineError> {
+    // find the labels first, so jumps can go forwards
+    let mut labels = HashMap::new();
+    let mut lines = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let error = |error| LineError { line: i + 1, error };
+        let mut code = line.split(';').next().unwrap().trim();
+        if let Some((label, rest)) = code.split_once(':') {
+            let label = label.trim();
+            if !is_label(label) {
+                return Err(error(AsmError::BadLabel(label.into())));
+            }
+            if labels.insert(label, lines.len()).is_some() {
+                return Err(error(AsmError::DuplicateLabel(label.into())));
+            }
+            code = rest.trim();
+        }
+        if !code.is_empty() {
+            lines.push((i + 1, code));
+        }
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, &(line, code))| {
+            Instruction::parse(code, index, &labels).map_err(|error| LineError { line, error })
+        })
+        .collect()
 }
 
 #[derive(Debug, Default, Clone)]
@@ -154,7 +414,7 @@ struct CrtState {
 impl CrtState {
     fn tick(&mut self, cpu: &CpuState) {
         self.buffer.push(
-            if (self.column() - 1..=self.column() + 1).contains(&cpu.x) {
+            if (self.column() - 1..=self.column() + 1).contains(&cpu.get(Reg::X)) {
                 '#'
             } else {
                 ' ' // spec says to use '.' here but it's less readable
@@ -166,8 +426,8 @@ impl CrtState {
         }
     }
 
-    fn column(&self) -> i16 {
-        (self.buffer.len() % 41) as i16
+    fn column(&self) -> i64 {
+        (self.buffer.len() % 41) as i64
     }
 }
 
@@ -175,9 +435,87 @@ impl CrtState {
 mod tests {
     use super::*;
 
+    fn run(source: &str, costs: CycleCosts) -> CpuState {
+        let mut device = Device::with_instructions(assemble(source).unwrap(), costs);
+        device.run(100_000);
+        assert!(device.cpu.done(), "still running");
+        device.cpu
+    }
+
     #[test]
     fn tests() {
-        assert_eq!(13140, part1(TEST_INPUT));
+        let instructions = assemble(TEST_INPUT).unwrap();
+        assert_eq!(13140, part1(&instructions, CycleCosts::default()));
+        let image = part2(&instructions, CycleCosts::default());
+        assert_eq!(
+            "##  ##  ##  ##  ##  ##  ##  ##  ##  ##  ",
+            image.lines().next().unwrap()
+        );
+    }
+
+    #[test]
+    fn programs() {
+        let source = "; x = (1 + 3 * 4) * 2, with a loop
+    addy 4      ; the counter
+loop:
+    addx 3
+    suby 1
+    jnzy loop
+    mulx 2
+done: ; a label on its own
+";
+        let cpu = run(source, CycleCosts::default());
+        assert_eq!([26, 0, 0, 0], cpu.registers);
+        // addy, then 4 times round addx, suby and jnzy, then mulx
+        assert_eq!(2 + 4 * (2 + 2 + 2) + 4, cpu.cycles);
+
+        let cheap = CycleCosts::default().with("add:1, jump:1").unwrap();
+        assert_eq!(1 + 4 * (1 + 2 + 1) + 4, run(source, cheap).cycles);
+        assert_eq!(None, CycleCosts::default().with("div:1"));
+        assert_eq!(None, CycleCosts::default().with("add:0"));
+
+        // registers as operands, and jumping off the end
+        let cpu = run("addy 5\naddz y\nmulz z\njzx 2\njnzx 100\naddw 1", CycleCosts::default());
+        assert_eq!([1, 5, 25, 0], cpu.registers);
+        assert_eq!(Ok(Instruction::Jump { reg: Reg::X, zero: true, offset: -2 }), "jzx -2".parse());
+
+        // squaring until it overflows wraps round instead of panicking
+        let cpu = run("addx 2\naddy 7\nloop: mulx x\nsuby 1\njnzy loop", CycleCosts::default());
+        assert_eq!(3_i64.wrapping_pow(1 << 7), cpu.get(Reg::X));
+        let cpu = run("addx 1\naddy 6\nloop: mulx x\nsuby 1\njnzy loop", CycleCosts::default());
+        assert_eq!(0, cpu.get(Reg::X));
+
+        // a program that halts before the checks in part 1 just stops there
+        let halts = assemble("addx 2\njzy 100\naddx 5").unwrap();
+        assert_eq!(3 * (20 + 60 + 100 + 140 + 180 + 220), part1(&halts, CycleCosts::default()));
+        assert_eq!("####", part2(&halts, CycleCosts::default()));
+
+        // and one that never halts fills the screen and stops
+        for source in ["jnzx 0", "loop: addx 0\njzy loop"] {
+            let forever = assemble(source).unwrap();
+            assert_eq!(20 + 60 + 100 + 140 + 180 + 220, part1(&forever, CycleCosts::default()));
+            let screen = part2(&forever, CycleCosts::default());
+            assert_eq!(vec![40; 6], screen.lines().map(str::len).collect::<Vec<_>>());
+        }
+        assert_eq!(Ok(Instruction::Add(Reg::X, Operand::Immediate(-11))), "addx -11".parse());
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source| assemble(source).unwrap_err().to_string();
+        assert_eq!("line 2: unknown instruction \"divx\"", error("noop\ndivx 2"));
+        assert_eq!("line 1: no register \"q\"", error("addq 1"));
+        assert_eq!("line 1: bad operand \"q\"", error("addx q"));
+        assert_eq!("line 3: wrong number of operands for addx", error("; hi\n\naddx"));
+        assert_eq!("line 1: wrong number of operands for noop", error("noop 1"));
+        assert_eq!("line 1: no label \"nowhere\"", error("jzx nowhere"));
+        assert_eq!("line 2: label \"a\" is already defined", error("a: noop\na:"));
+        assert_eq!("line 1: \"2go\" can't be a label", error("2go: noop"));
+        assert_eq!("line 1: \"2go\" can't be a label", error("jzx 2go"));
+        assert_eq!(
+            Err(AsmError::UnknownLabel("loop".into())),
+            "jnzx loop".parse::<Instruction>()
+        );
     }
     const TEST_INPUT: &str = "addx 15
 addx -11
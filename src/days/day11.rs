@@ -1,179 +1,185 @@
+use crate::expr::{EvalError, Expr};
 use std::collections::VecDeque;
+use std::fmt;
 use std::str::FromStr;
 
 pub fn solve(input: &str) -> String {
     let input = input.trim();
-    let p1 = part1(input);
-    let p2 = part2(input);
+    let p1 = part1(input).unwrap_or_else(|e| panic!("{e}"));
+    let p2 = part2(input).unwrap_or_else(|e| panic!("{e}"));
     format!("{p1}, {p2}")
 }
 
-fn part1(input: &str) -> usize {
-    let mut monkeys = parse_monkeys(input);
-    let modulus: u64 = monkeys.iter().map(|m| m.test.divisible_by).product();
+fn part1(input: &str) -> Result<usize, MonkeyError> {
+    let mut monkeys = parse_monkeys(input)?;
     for _ in 0..20 {
-        round::<true>(&mut monkeys, modulus);
+        round::<true>(&mut monkeys, 0)?;
     }
 
     let mut inspections: Vec<_> = monkeys.iter().map(|m| m.total_inspected).collect();
     inspections.sort_unstable();
 
-    inspections.pop().unwrap() * inspections.pop().unwrap()
+    Ok(inspections.pop().unwrap() * inspections.pop().unwrap())
 }
 
-fn part2(input: &str) -> usize {
-    let mut monkeys = parse_monkeys(input);
-    let modulus: u64 = monkeys.iter().map(|m| m.test.divisible_by).product();
+fn part2(input: &str) -> Result<usize, MonkeyError> {
+    let mut monkeys = parse_monkeys(input)?;
+    let modulus = monkeys
+        .iter()
+        .try_fold(1_i64, |m, monkey| m.checked_mul(monkey.test.divisible_by))
+        .ok_or(MonkeyError::ModulusOverflow)?;
 
     for _ in 0..10_000 {
-        round::<false>(&mut monkeys, modulus);
+        round::<false>(&mut monkeys, modulus)?;
     }
 
     let mut inspections: Vec<_> = monkeys.iter().map(|m| m.total_inspected).collect();
     inspections.sort_unstable();
 
-    inspections.pop().unwrap() * inspections.pop().unwrap()
+    Ok(inspections.pop().unwrap() * inspections.pop().unwrap())
 }
 
-fn round<const DIV_BY_3: bool>(monkeys: &mut [Monkey], modulus: u64) {
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MonkeyError {
+    /// A line of a monkey's notes that isn't what it should be.
+    BadLine(String),
+    NoSuchMonkey(usize),
+    /// The tests' divisors multiply to more than fits in an `i64`.
+    ModulusOverflow,
+    /// An operation that couldn't be worked out for some item.
+    Eval {
+        monkey: usize,
+        item: i64,
+        error: EvalError,
+    },
+}
+
+impl fmt::Display for MonkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MonkeyError::BadLine(line) => write!(f, "bad line {line:?}"),
+            MonkeyError::NoSuchMonkey(monkey) => write!(f, "there's no monkey {monkey}"),
+            MonkeyError::ModulusOverflow => write!(f, "the tests' divisors multiply to too much"),
+            MonkeyError::Eval {
+                monkey,
+                item,
+                error,
+            } => write!(f, "monkey {monkey} inspecting {item}: {error}"),
+        }
+    }
+}
+
+/// Without the division by 3 worry levels only matter modulo the product of
+/// the tests, `modulus`, so they're worked out modulo that. The division
+/// doesn't survive that, so with it they're worked out exactly.
+fn round<const DIV_BY_3: bool>(monkeys: &mut [Monkey], modulus: i64) -> Result<(), MonkeyError> {
     for monkey_id in 0..monkeys.len() {
-        // get the monkey out
-        let mut monkey = core::mem::take(&mut monkeys[monkey_id]);
-        monkey.total_inspected += monkey.items.len();
+        // get the items out
+        let items = core::mem::take(&mut monkeys[monkey_id].items);
+        monkeys[monkey_id].total_inspected += items.len();
+        let test = monkeys[monkey_id].test;
 
         // do stuff with the items
-        while let Some(mut item) = monkey.items.pop_front() {
-            // guard operation by the combined modulus of all monkeys
-            item %= modulus;
+        for mut item in items {
             // operation
-            item = monkey.operation.apply(&item);
-            if DIV_BY_3 {
-                item /= 3;
-            }
+            let operation = &monkeys[monkey_id].operation;
+            let new = if DIV_BY_3 {
+                operation.eval(&("old", item)).map(|n| n / 3)
+            } else {
+                operation.eval_mod(&("old", item), modulus)
+            };
+            item = new.map_err(|error| MonkeyError::Eval {
+                monkey: monkey_id,
+                item,
+                error,
+            })?;
 
             // test
-            let target_monkey = if item % monkey.test.divisible_by == 0 {
-                monkey.test.true_monkey
+            let target_monkey = if item % test.divisible_by == 0 {
+                test.true_monkey
             } else {
-                monkey.test.false_monkey
+                test.false_monkey
             };
 
             monkeys[target_monkey].items.push_back(item);
         }
-
-        // put the monkey back
-        monkeys[monkey_id] = monkey;
     }
+    Ok(())
 }
 
-fn parse_monkeys(input: &str) -> Vec<Monkey> {
-    input
+fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, MonkeyError> {
+    let monkeys: Vec<Monkey> = input
         .split("\n\n")
-        .map(|monkey_str| monkey_str.parse().unwrap())
-        .collect()
+        .map(|monkey_str| monkey_str.parse())
+        .collect::<Result<_, _>>()?;
+    let targets = monkeys
+        .iter()
+        .flat_map(|m| [m.test.true_monkey, m.test.false_monkey]);
+    if let Some(missing) = targets.filter(|&t| t >= monkeys.len()).min() {
+        return Err(MonkeyError::NoSuchMonkey(missing));
+    }
+    Ok(monkeys)
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 struct Monkey {
-    items: VecDeque<u64>,
-    operation: Operation,
+    items: VecDeque<i64>,
+    // new worry level, in terms of `old`
+    operation: Expr,
     test: Test,
     total_inspected: usize,
 }
 
-#[derive(Debug)]
-enum Operand {
-    Old,
-    Num(u64),
-}
-use Operand::*;
-
-impl FromStr for Operand {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s == "old" {
-            Ok(Self::Old)
-        } else {
-            Ok(Self::Num(s.parse().unwrap()))
-        }
-    }
-}
-
-#[derive(Debug)]
-enum Operation {
-    Multiply(Operand),
-    Add(Operand),
-}
-use Operation::*;
-
-impl Operation {
-    fn apply(&self, old: &u64) -> u64 {
-        match self {
-            Multiply(Old) => old * old,
-            Multiply(Num(n)) => old * n,
-            Add(Old) => old + old,
-            Add(Num(n)) => old + n,
-        }
-    }
-}
-
-impl Default for Operation {
-    fn default() -> Self {
-        Self::Add(Old)
-    }
-}
-
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Copy)]
 struct Test {
-    divisible_by: u64,
+    divisible_by: i64,
     true_monkey: usize,
     false_monkey: usize,
 }
 
+/// The next line of a monkey's notes, with `prefix` taken off and the rest
+/// read by `parse`.
+fn field<'a, T>(
+    lines: &mut impl Iterator<Item = &'a str>,
+    prefix: &str,
+    parse: impl FnOnce(&'a str) -> Option<T>,
+) -> Result<T, MonkeyError> {
+    let line = lines.next().unwrap_or_default();
+    line.strip_prefix(prefix)
+        .and_then(parse)
+        .ok_or_else(|| MonkeyError::BadLine(line.to_string()))
+}
+
 impl FromStr for Monkey {
-    type Err = ();
+    type Err = MonkeyError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-        assert!(lines.next().unwrap().starts_with("Monkey "));
+        let mut lines = s.lines().map(str::trim);
+        field(&mut lines, "Monkey ", |_| Some(()))?;
 
         // items
-        let (_, items) = lines.next().unwrap().split_once("items: ").unwrap();
-        let items = items.split(", ").map(|i| i.parse().unwrap()).collect();
-
-        // operation
-        let (_, operation) = lines
-            .next()
-            .unwrap()
-            .split_once("Operation: new = old ")
-            .unwrap();
-        let operation = if let Some((_, num)) = operation.split_once("* ") {
-            Operation::Multiply(num.parse().unwrap())
-        } else {
-            let (_, num) = operation.split_once("+ ").unwrap();
-            Operation::Add(num.parse().unwrap())
-        };
+        let items = field(&mut lines, "Starting items:", |items| {
+            items
+                .split(',')
+                .map(str::trim)
+                .filter(|i| !i.is_empty())
+                .map(|i| i.parse().ok())
+                .collect()
+        })?;
+
+        // operation, which can only use `old`
+        let operation = field(&mut lines, "Operation: new = ", |operation| {
+            let operation: Expr = operation.parse().ok()?;
+            let only_old = operation.variables().iter().all(|&v| v == "old");
+            only_old.then_some(operation)
+        })?;
 
         // test
-        let divisible_by = lines
-            .next()
-            .unwrap()
-            .split_once("divisible by ")
-            .map(|(_, db)| db.parse().unwrap())
-            .unwrap();
-        let true_monkey = lines
-            .next()
-            .unwrap()
-            .split_once("true: throw to monkey ")
-            .map(|(_, monkey)| monkey.parse().unwrap())
-            .unwrap();
-        let false_monkey = lines
-            .next()
-            .unwrap()
-            .split_once("false: throw to monkey ")
-            .map(|(_, monkey)| monkey.parse().unwrap())
-            .unwrap();
+        let divisible_by = field(&mut lines, "Test: divisible by ", |n| {
+            n.parse().ok().filter(|&n: &i64| n > 0)
+        })?;
+        let true_monkey = field(&mut lines, "If true: throw to monkey ", |n| n.parse().ok())?;
+        let false_monkey = field(&mut lines, "If false: throw to monkey ", |n| n.parse().ok())?;
         let test = Test {
             divisible_by,
             true_monkey,
@@ -195,8 +201,71 @@ mod tests {
 
     #[test]
     fn tests() {
-        assert_eq!(10605, part1(TEST_INPUT));
-        assert_eq!(2713310158, part2(TEST_INPUT));
+        assert_eq!(Ok(10605), part1(TEST_INPUT));
+        assert_eq!(Ok(2713310158), part2(TEST_INPUT));
+    }
+
+    #[test]
+    fn any_operation() {
+        let monkey: Monkey = TEST_INPUT
+            .replace("old * 19", "(old + 1) * 2 - old / 4")
+            .split("\n\n")
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(Ok(141), monkey.operation.eval(&("old", 79)));
+    }
+
+    #[test]
+    fn any_operation_rounds() {
+        // cubing overflows an i64 when it's worked out exactly, but not
+        // when it's reduced as it goes
+        let cubed = TEST_INPUT.replace("old * old", "old * old * old");
+        assert!(matches!(
+            part1(&cubed),
+            Err(MonkeyError::Eval {
+                error: EvalError::Overflow,
+                ..
+            })
+        ));
+        assert_eq!(Ok(2901438221), part2(&cubed));
+
+        // halving can't be done modulo anything, but is fine in part 1
+        let halved = TEST_INPUT.replace("old + 6", "old / 2 + 6");
+        assert!(part1(&halved).is_ok());
+        assert!(matches!(
+            part2(&halved),
+            Err(MonkeyError::Eval {
+                monkey: 1,
+                error: EvalError::ModularDivision,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn bad_notes() {
+        let bad_line = |line: &str| Err(MonkeyError::BadLine(line.to_string()));
+        let notes = TEST_INPUT.replace("79, 98", "79, x");
+        assert_eq!(bad_line("Starting items: 79, x"), part1(&notes));
+        let notes = TEST_INPUT.replace("old + 3", "old + new");
+        assert_eq!(bad_line("Operation: new = old + new"), part1(&notes));
+        let notes = TEST_INPUT.replace("by 13", "by 0");
+        assert_eq!(bad_line("Test: divisible by 0"), part1(&notes));
+        let notes = TEST_INPUT.replace("If false: throw to monkey 1", "");
+        assert_eq!(bad_line(""), part1(&notes));
+        let notes = TEST_INPUT.replace("monkey 3", "monkey 7");
+        assert_eq!(Err(MonkeyError::NoSuchMonkey(7)), part1(&notes));
+
+        // no worries are too big for part 1, but the modulus is
+        let notes = TEST_INPUT.replace("by 23", "by 4611686018427387904");
+        assert!(part1(&notes).is_ok());
+        assert_eq!(Err(MonkeyError::ModulusOverflow), part2(&notes));
+
+        // a monkey can start with nothing
+        let notes = TEST_INPUT.replace(" 74\n", "\n");
+        assert!(part1(&notes).is_ok());
     }
 
     const TEST_INPUT: &str = "Monkey 0:
@@ -1,3 +1,6 @@
+use crate::render::{heat, Image, Rgb};
+use crate::stats;
+use crate::viz::{Color, Disabled, Frame, Observer};
 use std::collections::BTreeSet;
 
 pub fn solve(input: &str) -> String {
@@ -60,8 +63,9 @@ impl PartialOrd for Distance {
     }
 }
 
-fn hike_the_mountain<F>(input: &str, mut check: F) -> usize
+fn hike_the_mountain<O, F>(input: &str, observer: &mut O, mut check: F) -> usize
 where
+    O: Observer,
     F: FnMut(Option<(GridEntry, usize)>) -> ControlFlow,
 {
     let width = input.find('\n').unwrap();
@@ -133,10 +137,23 @@ where
 
     // priority queue starts with the end
     let mut queue = BTreeSet::from([grid[end_xy.0][end_xy.1]]);
+    // distance of the last frame drawn
+    let mut frame_distance = None;
+    // counted once on the way out, since the counters are behind a lock
+    let mut pops = 0;
 
     while let Some(current) = (&mut queue).pop_first() {
+        pops += 1;
+        if O::ENABLED && frame_distance != Some(current.distance) {
+            frame_distance = Some(current.distance);
+            observer.frame(draw(&grid, current.distance.unwrap(), start_index, end_index));
+        }
+
         match check(Some((current, start_index))) {
-            ControlFlow::Return(val) => return val,
+            ControlFlow::Return(val) => {
+                stats::count("queue pops", pops);
+                return val;
+            }
             ControlFlow::Continue => continue,
             ControlFlow::FallThrough => {}
         }
@@ -194,6 +211,7 @@ where
         }
     }
 
+    stats::count("queue pops", pops);
     let ControlFlow::Return(val) = check(None) else {
         panic!("check closure didn't handle function end");
     };
@@ -201,6 +219,25 @@ where
     val
 }
 
+// one frame per distance from the goal, with the frontier highlighted
+fn draw(grid: &[Vec<GridEntry>], frontier: usize, start: usize, end: usize) -> Frame {
+    let mut frame = Frame::new(grid[0].len(), grid.len());
+    for (y, row) in grid.iter().enumerate() {
+        for (x, entry) in row.iter().enumerate() {
+            let (ch, color) = match entry.distance {
+                _ if entry.index == start => ('S', Color::Red),
+                _ if entry.index == end => ('E', Color::Green),
+                Known(d) if d == frontier => (entry.height as char, Color::Yellow),
+                Known(_) => (entry.height as char, Color::Cyan),
+                Unknown => (entry.height as char, Color::Gray),
+            };
+            frame.set(x, y, ch, color);
+        }
+    }
+    frame.set_caption(format!("distance from goal: {frontier}"));
+    frame
+}
+
 enum ControlFlow {
     // stop searching the grid and return the value
     Return(usize),
@@ -210,22 +247,29 @@ enum ControlFlow {
     FallThrough,
 }
 
+fn find_start(vals: Option<(GridEntry, usize)>) -> ControlFlow {
+    let (current, start_index) = vals.unwrap();
+
+    if current.index == start_index {
+        // found the start! return the smallest distance (must already be found)
+        ControlFlow::Return(current.distance.unwrap())
+    } else {
+        ControlFlow::FallThrough
+    }
+}
+
 fn part1(input: &str) -> usize {
-    hike_the_mountain(input, |vals| {
-        let (current, start_index) = vals.unwrap();
+    hike_the_mountain(input, &mut Disabled, find_start)
+}
 
-        if current.index == start_index {
-            // found the start! return the smallest distance (must already be found)
-            ControlFlow::Return(current.distance.unwrap())
-        } else {
-            ControlFlow::FallThrough
-        }
-    })
+/// Show the search spreading out from the goal until it reaches the start.
+pub fn visualize(input: &str, observer: &mut impl Observer) {
+    hike_the_mountain(input, observer, find_start);
 }
 
 fn part2(input: &str) -> usize {
     let mut best = usize::MAX;
-    hike_the_mountain(input, |vals| {
+    hike_the_mountain(input, &mut Disabled, |vals| {
         let Some((current, _)) = vals else {
             return ControlFlow::Return(best);
         };
@@ -242,6 +286,65 @@ fn part2(input: &str) -> usize {
     })
 }
 
+/// Distance to the goal as a heat map, with the shortest path from the start
+/// drawn on top.
+pub fn render(input: &str) -> Image {
+    let rows: Vec<&[u8]> = input.lines().map(str::as_bytes).collect();
+    let width = rows[0].len();
+    let height = |index: usize| match rows[index / width][index % width] {
+        b'S' => b'a',
+        b'E' => b'z',
+        h => h,
+    };
+
+    // run the whole search, keeping the best distance seen for each square
+    let mut distances: Vec<Option<usize>> = vec![None; width * rows.len()];
+    let mut start = 0;
+    hike_the_mountain(input, &mut Disabled, |vals| {
+        let Some((current, start_index)) = vals else {
+            return ControlFlow::Return(0);
+        };
+        start = start_index;
+        let distance = current.distance.unwrap();
+        let best = &mut distances[current.index];
+        *best = Some(best.map_or(distance, |b| b.min(distance)));
+        ControlFlow::FallThrough
+    });
+
+    let max = distances.iter().flatten().max().copied().unwrap_or(0);
+    let mut image = Image::new(width, rows.len(), Rgb::NAVY);
+    for (index, distance) in distances.iter().enumerate() {
+        if let Some(d) = distance {
+            // brightest at the goal
+            let color = heat((max - d) as f64, 0.0, max as f64);
+            image.set(index % width, index / width, color);
+        }
+    }
+
+    // walk downhill in distance from the start, only taking climbable steps
+    let mut at = start;
+    while let Some(distance @ 1..) = distances[at] {
+        let (x, y) = (at % width, at / width);
+        image.set(x, y, Rgb::BLUE);
+        let neighbors = [
+            (x > 0).then(|| at - 1),
+            (x + 1 < width).then(|| at + 1),
+            (y > 0).then(|| at - width),
+            (y + 1 < rows.len()).then(|| at + width),
+        ];
+        let Some(next) = neighbors.into_iter().flatten().find(|&n| {
+            distances[n] == Some(distance - 1) && height(n) <= height(at) + 1
+        }) else {
+            break;
+        };
+        at = next;
+    }
+    image.set(at % width, at / width, Rgb::GREEN);
+    image.set(start % width, start / width, Rgb::RED);
+
+    image.scaled(4)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -252,6 +355,15 @@ mod tests {
         assert_eq!(29, part2(TEST_INPUT));
     }
 
+    #[test]
+    fn frames() {
+        let mut frames = Vec::new();
+        visualize(TEST_INPUT, &mut frames);
+        // one per distance 0..=31
+        assert_eq!(32, frames.len());
+        assert_eq!("distance from goal: 31", frames[31].caption());
+    }
+
     const TEST_INPUT: &str = "Sabqponm
 abcryxxl
 accszExk
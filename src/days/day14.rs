@@ -6,6 +6,8 @@ use nom::{
     sequence::separated_pair,
     IResult,
 };
+use crate::render::{Image, Rgb};
+use crate::viz::{Color, Disabled, Frame, Observer};
 use std::collections::BTreeSet;
 
 pub fn solve(input: &str) -> String {
@@ -46,11 +48,21 @@ fn rocks(i: &str) -> IResult<&str, Vec<Rock>> {
 
 fn part1(input: &str) -> usize {
     let (_, rocks) = rocks(input).unwrap();
-    simulate(&rocks)
+    simulate(&rocks, &mut Disabled).len()
+}
+
+/// Show each grain of sand coming to rest, as in part 1.
+pub fn visualize(input: &str, observer: &mut impl Observer) {
+    let (_, rocks) = rocks(input).unwrap();
+    simulate(&rocks, observer);
 }
 
 fn part2(input: &str) -> usize {
-    let (_, mut rocks) = rocks(input).unwrap();
+    let (_, rocks) = rocks(input).unwrap();
+    simulate(&with_floor(rocks), &mut Disabled).len()
+}
+
+fn with_floor(mut rocks: Vec<Rock>) -> Vec<Rock> {
     let lowest_rock = rocks.iter().map(|r| r.lowest_point()).max().unwrap();
     let floor_y = lowest_rock + 2;
 
@@ -72,37 +84,65 @@ fn part2(input: &str) -> usize {
     };
 
     rocks.push(floor);
-
-    simulate(&rocks)
+    rocks
 }
 
-fn simulate(rocks: &[Rock]) -> usize {
-    let lowest_rock = rocks.iter().map(|r| r.lowest_point()).max().unwrap();
+/// The sand pile from part 2, sand in yellow on grey rock.
+pub fn render(input: &str) -> Image {
+    let (_, rocks) = rocks(input).unwrap();
+    let rocks = with_floor(rocks);
+    let rock = rock_pixels(&rocks);
+    let sand = simulate(&rocks, &mut Disabled);
 
-    let mut static_objects: BTreeSet<Coord> = BTreeSet::new();
-    let mut sand_count = 0;
+    let left = rock.iter().map(|c| c.x).min().unwrap();
+    let right = rock.iter().map(|c| c.x).max().unwrap();
+    let bottom = rock.iter().map(|c| c.y).max().unwrap();
 
-    // add each rock pixel to the static objects set
+    let mut image = Image::new((right - left + 1) as usize, bottom as usize + 1, Rgb::NAVY);
+    for c in &rock {
+        image.set((c.x - left) as usize, c.y as usize, Rgb::GRAY);
+    }
+    for c in &sand {
+        // darker sand deeper down
+        let color = Rgb::YELLOW.mix(Rgb(120, 80, 20), c.y as f64 / bottom as f64);
+        image.set((c.x - left) as usize, c.y as usize, color);
+    }
+    image.set((500 - left) as usize, 0, Rgb::RED);
+    image.scaled(3)
+}
+
+fn rock_pixels(rocks: &[Rock]) -> BTreeSet<Coord> {
+    let mut pixels = BTreeSet::new();
     for rock in rocks {
         for [from, to] in rock.coords.array_windows() {
             if from.x == to.x {
                 for y in from.y.min(to.y)..=from.y.max(to.y) {
-                    static_objects.insert(Coord { x: from.x, y });
+                    pixels.insert(Coord { x: from.x, y });
                 }
             } else {
                 assert_eq!(from.y, to.y);
                 for x in from.x.min(to.x)..=from.x.max(to.x) {
-                    static_objects.insert(Coord { x, y: from.y });
+                    pixels.insert(Coord { x, y: from.y });
                 }
             }
         }
     }
+    pixels
+}
+
+// drop sand until it falls forever or plugs the source, returning where each
+// grain came to rest
+fn simulate<O: Observer>(rocks: &[Rock], observer: &mut O) -> BTreeSet<Coord> {
+    let lowest_rock = rocks.iter().map(|r| r.lowest_point()).max().unwrap();
+
+    let rock = rock_pixels(rocks);
+    let mut resting: BTreeSet<Coord> = BTreeSet::new();
 
     loop {
         // drop sand
         let mut sand = Coord { x: 500, y: 0 };
 
-        let overlaps = |sand: &Coord| static_objects.contains(sand);
+        let overlaps = |sand: &Coord| rock.contains(sand) || resting.contains(sand);
 
         while sand.y < lowest_rock {
             let mut test = sand.clone();
@@ -139,8 +179,11 @@ fn simulate(rocks: &[Rock]) -> usize {
         }
 
         // save stopped sand
-        static_objects.insert(sand.clone());
-        sand_count += 1;
+        resting.insert(sand.clone());
+
+        if O::ENABLED {
+            observer.frame(draw(&rock, &resting));
+        }
 
         // check if source is plugged
         if let Coord { x: 500, y: 0 } = sand {
@@ -148,7 +191,25 @@ fn simulate(rocks: &[Rock]) -> usize {
         }
     }
 
-    sand_count
+    resting
+}
+
+fn draw(rock: &BTreeSet<Coord>, sand: &BTreeSet<Coord>) -> Frame {
+    let xs = || rock.iter().chain(sand).map(|c| c.x).chain([500]);
+    let left = xs().min().unwrap();
+    let right = xs().max().unwrap();
+    let bottom = rock.iter().chain(sand).map(|c| c.y).max().unwrap();
+
+    let mut frame = Frame::new((right - left + 1) as usize, bottom as usize + 1);
+    frame.set((500 - left) as usize, 0, '+', Color::Red);
+    for c in rock {
+        frame.set((c.x - left) as usize, c.y as usize, '#', Color::Gray);
+    }
+    for c in sand {
+        frame.set((c.x - left) as usize, c.y as usize, 'o', Color::Yellow);
+    }
+    frame.set_caption(format!("sand at rest: {}", sand.len()));
+    frame
 }
 
 #[cfg(test)]
@@ -161,6 +222,29 @@ mod tests {
         assert_eq!(93, part2(TEST_INPUT));
     }
 
+    #[test]
+    fn frames() {
+        let mut frames = Vec::new();
+        visualize(TEST_INPUT, &mut frames);
+        assert_eq!(24, frames.len());
+        let last = frames.last().unwrap();
+        assert_eq!("sand at rest: 24", last.caption());
+        assert_eq!(
+            "......+...
+..........
+......o...
+.....ooo..
+....#ooo##
+...o#ooo#.
+..###ooo#.
+....oooo#.
+.o.ooooo#.
+#########.
+",
+            last.to_plain().replace(' ', ".")
+        );
+    }
+
     const TEST_INPUT: &str = "498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9";
 }
@@ -5,6 +5,8 @@ use nom::{
     sequence::{pair, separated_pair, tuple},
     IResult,
 };
+use crate::render::{heat, Image, Rgb};
+use crate::stats;
 use std::collections::BTreeSet;
 
 pub fn solve(input: &str) -> String {
@@ -111,6 +113,7 @@ fn sensors(i: &str) -> IResult<&str, Vec<Sensor>> {
 }
 
 fn part1(input: &str) -> usize {
+    let _span = stats::span("part1");
     const LINE: i32 = if cfg!(test) { 10 } else { 2_000_000 };
 
     let (_, sensors) = sensors(input).unwrap();
@@ -128,10 +131,15 @@ fn part1(input: &str) -> usize {
     blackout.len()
 }
 
+const RANGE: (i32, i32) = if cfg!(test) { (0, 20) } else { (0, 4_000_000) };
+
 fn part2(input: &str) -> u64 {
-    const RANGE: (i32, i32) = if cfg!(test) { (0, 20) } else { (0, 4_000_000) };
+    let _span = stats::span("part2");
+    let (_, sensors) = sensors(input).unwrap();
+    distress_beacon(sensors).tuning_frequency()
+}
 
-    let (_, mut sensors) = sensors(input).unwrap();
+fn distress_beacon(mut sensors: Vec<Sensor>) -> Coord {
     sensors.sort_unstable();
     let sensors = sensors;
 
@@ -149,7 +157,8 @@ fn part2(input: &str) -> u64 {
 
         let x = first_missing(&regions);
         if x <= RANGE.1 {
-            return Coord { x, y }.tuning_frequency();
+            stats::count("rows scanned", (y - RANGE.0 + 1) as u64);
+            return Coord { x, y };
         }
 
         regions.clear();
@@ -158,6 +167,61 @@ fn part2(input: &str) -> u64 {
     panic!("not found")
 }
 
+/// Sensor coverage over the part 2 search area, brighter where more sensors
+/// overlap, with the distress beacon boxed in white.
+pub fn render(input: &str) -> Image {
+    const SIZE: i32 = 800;
+
+    let (_, sensors) = sensors(input).unwrap();
+    let span = RANGE.1 - RANGE.0 + 1;
+    let pixels = span.min(SIZE);
+    // search area units per pixel, rounded up
+    let step = (span + pixels - 1) / pixels;
+    let to_pixel = |c: &Coord| {
+        let x = (c.x - RANGE.0).div_euclid(step);
+        let y = (c.y - RANGE.0).div_euclid(step);
+        (x as usize, y as usize)
+    };
+
+    let mut image = Image::new(pixels as usize, pixels as usize, Rgb::NAVY);
+    for py in 0..pixels {
+        for px in 0..pixels {
+            let at = Coord {
+                x: RANGE.0 + px * step + step / 2,
+                y: RANGE.0 + py * step + step / 2,
+            };
+            let covered = sensors
+                .iter()
+                .filter(|s| s.location.distance(&at) <= s.location.distance(&s.beacon))
+                .count();
+            if covered > 0 {
+                image.set(px as usize, py as usize, heat(covered as f64, 0.0, 4.0));
+            }
+        }
+    }
+
+    let mut mark = |c: &Coord, radius: i32, color: Rgb| {
+        let (x, y) = to_pixel(c);
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                // just the outline for bigger marks
+                if radius < 2 || dx.abs().max(dy.abs()) == radius {
+                    let x = x.wrapping_add_signed(dx as isize);
+                    let y = y.wrapping_add_signed(dy as isize);
+                    image.set(x, y, color);
+                }
+            }
+        }
+    };
+    for s in &sensors {
+        mark(&s.location, 1, Rgb::GREEN);
+        mark(&s.beacon, 1, Rgb::BLUE);
+    }
+    mark(&distress_beacon(sensors), 6, Rgb::WHITE);
+
+    image.scaled((SIZE / pixels) as usize)
+}
+
 // regions must be sorted first, otherwise this may return an incorrect value
 fn first_missing(regions: &[(i32, i32)]) -> i32 {
     let mut first_missing = 0;
@@ -1,4 +1,4 @@
-#![allow(dead_code, unused_variables)]
+use crate::{fuzz, params, stats};
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -8,16 +8,32 @@ use nom::{
     sequence::{pair, tuple},
     IResult,
 };
-use std::collections::BTreeSet;
+use rand::prelude::*;
 use std::collections::HashMap;
 
-const MINUTES: usize = 30;
+// override with `-p day16.seed=<n>` to replay or explore other runs
+const DEFAULT_SEED: u64 = 16;
 
+/// `-p day16.fuzz=<n>` checks part 1 by fuzzing for up to `n` tries as well,
+/// printing each new best and the seed, worker and try it was found on. The
+/// fuzzer is seeded with `-p day16.seed=<s>`, so a run can be replayed.
 pub fn solve(input: &str) -> String {
-    // let p1 = part1(input);
-    // let p2 = part2(input);
-    // format!("{p1}, {p2}")
-    "giving up for now".into()
+    let p1 = part1(input);
+    let p2 = part2(input);
+    let mut out = format!("{p1}, {p2}");
+    if let Some(tries) = params::get::<u64>("day16.fuzz") {
+        let config = fuzz::Config {
+            seed: params::get("day16.seed").unwrap_or(DEFAULT_SEED),
+            max_iters: Some(tries),
+            target_score: Some(p1 as u64),
+            ..fuzz::Config::default()
+        };
+        let outcome = fuzz_part1(input, &config, |_, score, found| {
+            println!("best: {score} ({found})")
+        });
+        out += &format!(", fuzzed {} in {} tries", outcome.score, outcome.iterations);
+    }
+    out
 }
 
 #[derive(Debug)]
@@ -65,155 +81,209 @@ fn valves(i: &str) -> IResult<&str, Vec<ParsedValve>> {
     separated_list1(newline, valve)(i)
 }
 
-#[derive(Debug, Clone)]
-struct Valve {
-    // Some(val) when available,
-    // None when already opened
-    flow_rate: Option<u32>,
-    connections: Vec<usize>,
+/// Just the valves worth opening, and how long it takes to walk between them.
+struct Network {
+    flow: Vec<u32>,
+    // distance[a][b] in minutes, between valves with flow
+    distance: Vec<Vec<u32>>,
+    // minutes from AA to each valve with flow
+    from_start: Vec<u32>,
 }
 
-fn part1(input: &str) -> u32 {
-    let (_, parsed_valves) = valves(input).unwrap();
+impl Network {
+    fn new(parsed: &[ParsedValve]) -> Self {
+        let ids: HashMap<&str, usize> = parsed
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.name.as_str(), i))
+            .collect();
 
-    let mut valves = Vec::new();
-    let mut valve_ids: HashMap<&str, usize> = HashMap::new();
+        // all pairs shortest paths over every valve
+        let n = parsed.len();
+        let mut all = vec![vec![u32::MAX / 2; n]; n];
+        for (i, v) in parsed.iter().enumerate() {
+            all[i][i] = 0;
+            for c in &v.connections {
+                all[i][ids[c.as_str()]] = 1;
+            }
+        }
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    all[i][j] = all[i][j].min(all[i][k] + all[k][j]);
+                }
+            }
+        }
 
-    for v in &parsed_valves {
-        assert!(v.connections.len() < 256);
-        valve_ids.insert(v.name.as_str(), valves.len());
-        valves.push(Valve {
-            flow_rate: Some(v.flow_rate),
-            connections: Vec::new(),
-        });
+        let useful: Vec<usize> = (0..n).filter(|&i| parsed[i].flow_rate > 0).collect();
+        assert!(useful.len() < 32, "too many valves for a u32 set");
+        let start = ids["AA"];
+        Self {
+            flow: useful.iter().map(|&i| parsed[i].flow_rate).collect(),
+            distance: useful
+                .iter()
+                .map(|&a| useful.iter().map(|&b| all[a][b]).collect())
+                .collect(),
+            from_start: useful.iter().map(|&i| all[start][i]).collect(),
+        }
     }
 
-    for (valve, parsed) in valves.iter_mut().zip(parsed_valves.iter()) {
-        for name in &parsed.connections {
-            valve.connections.push(valve_ids[name.as_str()])
+    /// The most pressure released by opening exactly each set of valves
+    /// (as a bitmask) within `minutes`, starting from AA.
+    fn best_per_set(&self, minutes: u32) -> Vec<u32> {
+        let mut best = vec![0; 1 << self.flow.len()];
+        let mut calls = 0;
+        for (valve, &walk) in self.from_start.iter().enumerate() {
+            calls += self.open(valve, minutes.saturating_sub(walk + 1), 0, 0, &mut best);
         }
+        stats::count("valves opened", calls);
+        best
     }
 
-    for v in &valves {
-        println!("{v:?}");
-    }
+    // open `valve` with `time_left` minutes to go, then try every valve
+    // still closed next, giving how many valves were opened on the way
+    fn open(
+        &self,
+        valve: usize,
+        time_left: u32,
+        opened: u32,
+        released: u32,
+        best: &mut [u32],
+    ) -> u64 {
+        if time_left == 0 {
+            return 0;
+        }
+        let opened = opened | 1 << valve;
+        let released = released + self.flow[valve] * time_left;
+        best[opened as usize] = best[opened as usize].max(released);
 
-    let mut threads = Vec::new();
-    let num: usize = if cfg!(test) {
-        1
-    } else {
-        std::thread::available_parallelism().unwrap().into()
-    };
-    for _ in 0..num {
-        let valves = valves.clone();
-        let jh = std::thread::spawn(move || fuzz(valves, usize::MAX));
-        threads.push(jh);
-    }
-    for t in threads {
-        return t.join().unwrap();
+        let mut calls = 1;
+        for next in 0..self.flow.len() {
+            let cost = self.distance[valve][next] + 1;
+            if opened & 1 << next == 0 && cost < time_left {
+                calls += self.open(next, time_left - cost, opened, released, best);
+            }
+        }
+        calls
     }
-    0
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Action {
-    Open,
-    Move(u8),
-}
-use Action::*;
-
-fn fuzz(valves: Vec<Valve>, iters: usize) -> u32 {
-    use rand::prelude::*;
-    let mut rng = rand::thread_rng();
-
-    let mut best = 0;
-    let mut seen = BTreeSet::new();
-    let mut corpus = Vec::from([([Open; MINUTES], 100)]);
-
-    for _iter in 0..iters {
-        //println!("FUZZ RUN {iter}");
-        let mut fuzz_valves = valves.clone();
-
-        let (mut actions, _score) = corpus.choose_weighted(&mut rng, |item| item.1).unwrap();
-        //let (mut actions, score) = corpus.choose(&mut rng).unwrap();
-
-        // mutate
-        const CORRUPTION_FACTOR: u8 = 8;
-        for _ in 0..=(rng.gen::<u8>() % CORRUPTION_FACTOR) {
-            // pick an index
-            let action = actions.choose_mut(&mut rng).unwrap();
-            *action = match action {
-                Open => Move(rng.gen::<u8>()),
-                Move(m) => {
-                    if rng.gen() {
-                        Open
-                    } else {
-                        if rng.gen() {
-                            Move(m.wrapping_add(1))
-                        } else {
-                            Move(m.wrapping_sub(1))
-                        }
-                    }
-                }
-            };
-        }
+// the order to open valves in, scored by walking it for 30 minutes
+impl fuzz::Target for Network {
+    type Genome = Vec<usize>;
 
-        let result = fuzz_one(&mut fuzz_valves, &actions);
+    fn mutate(&self, order: &mut Vec<usize>, rng: &mut StdRng) {
+        if order.is_empty() {
+            return;
+        }
+        let a = rng.gen_range(0..order.len());
+        let b = rng.gen_range(0..order.len());
+        order.swap(a, b);
+    }
 
-        //if result > best {
-        if !seen.contains(&result) {
-            corpus.push((actions.clone(), result.0));
-            best = best.max(result.0);
-            seen.insert(result);
-            println!("best: {best} corpus size: {}", corpus.len());
-            if cfg!(test) && best == 1651 {
-                return best;
+    fn score(&self, order: &Vec<usize>) -> u64 {
+        let mut time_left = 30;
+        let mut released = 0;
+        let Some(&first) = order.first() else {
+            return 0;
+        };
+        let mut walk = self.from_start[first];
+        for (i, &valve) in order.iter().enumerate() {
+            if walk + 1 >= time_left {
+                break;
+            }
+            time_left -= walk + 1;
+            released += self.flow[valve] * time_left;
+            if let Some(&next) = order.get(i + 1) {
+                walk = self.distance[valve][next];
             }
         }
+        released as u64
+    }
+
+    // which valve comes first
+    fn coverage(&self, order: &Vec<usize>) -> u64 {
+        order.first().map_or(0, |&v| v as u64)
     }
-    best
 }
 
-fn fuzz_one(valves: &mut [Valve], actions: &[Action]) -> (u32, u64) {
-    let mut steam = 0;
-    let mut valve = 0;
+/// Fuzz the order to open valves in for part 1, starting from opening them
+/// in input order.
+fn fuzz_part1(
+    input: &str,
+    config: &fuzz::Config,
+    on_best: impl Fn(&Vec<usize>, u64, fuzz::Found) + Sync,
+) -> fuzz::Outcome<Vec<usize>> {
+    let (_, parsed) = valves(input).unwrap();
+    let network = Network::new(&parsed);
+    let seed = (0..network.flow.len()).collect();
+    fuzz::fuzz(&network, vec![seed], config, on_best)
+}
 
-    let mut paths: u64 = 0;
+fn part1(input: &str) -> u32 {
+    let (_, parsed) = valves(input).unwrap();
+    let network = Network::new(&parsed);
+    network.best_per_set(30).into_iter().max().unwrap()
+}
 
-    for (minute, action) in actions.iter().take(MINUTES).enumerate() {
-        let minute = minute + 1;
+fn part2(input: &str) -> u32 {
+    let (_, parsed) = valves(input).unwrap();
+    let network = Network::new(&parsed);
+    let mut best = network.best_per_set(26);
 
-        match action {
-            Action::Open => {
-                if let Some(flow) = valves[valve].flow_rate.take() {
-                    steam += (MINUTES - minute) * flow as usize;
-                }
-            }
-            Action::Move(idx) => {
-                let conns = &valves[valve].connections;
-                let new = conns[*idx as usize % conns.len()];
-                valve = new;
-                let hash = (valve << 0) ^ new;
-                paths = paths | hash as u64;
+    // make best[set] the best over any subset of it, so the elephant can
+    // take whatever is left over after our valves
+    for bit in 0..network.flow.len() {
+        for set in 0..best.len() {
+            if set & 1 << bit != 0 {
+                best[set] = best[set].max(best[set ^ 1 << bit]);
             }
         }
     }
 
-    (steam as u32, paths)
-}
-
-fn part2(_input: &str) -> u64 {
-    0
+    let all = best.len() - 1;
+    (0..best.len())
+        .map(|ours| best[ours] + best[all ^ ours])
+        .max()
+        .unwrap()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Mutex;
 
     #[test]
     fn tests() {
         assert_eq!(1651, part1(TEST_INPUT));
-        assert_eq!(0, part2(TEST_INPUT));
+        assert_eq!(1707, part2(TEST_INPUT));
+    }
+
+    #[test]
+    fn fuzzed() {
+        let config = fuzz::Config {
+            seed: DEFAULT_SEED,
+            max_iters: Some(10_000),
+            target_score: Some(1651),
+            ..fuzz::Config::default()
+        };
+        let log = Mutex::new(Vec::new());
+        let outcome = fuzz_part1(TEST_INPUT, &config, |_, score, found| {
+            log.lock().unwrap().push((score, found))
+        });
+        assert_eq!(1651, outcome.score);
+
+        // the same seed finds the same bests in the same places
+        let log = log.into_inner().unwrap();
+        assert_eq!((1651, outcome.iterations), {
+            let (score, found) = log.last().unwrap();
+            (*score, found.iteration)
+        });
+        let again = Mutex::new(Vec::new());
+        fuzz_part1(TEST_INPUT, &config, |_, score, found| {
+            again.lock().unwrap().push((score, found))
+        });
+        assert_eq!(log, again.into_inner().unwrap());
     }
 
     const TEST_INPUT: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
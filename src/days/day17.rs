@@ -1,5 +1,7 @@
 use std::collections::BTreeMap;
 
+use crate::render::{heat, Image, Rgb};
+use crate::viz::{Color, Disabled, Frame, Observer};
 use nom::{branch::alt, bytes::complete::tag, combinator::map, multi::many1, IResult};
 
 pub fn solve(input: &str) -> String {
@@ -69,20 +71,38 @@ fn jets(i: &str) -> IResult<&str, Vec<Jet>> {
     )))(i)
 }
 
-#[allow(dead_code)]
-fn print_grid(grid: &[[Block; 9]]) {
-    for layer in grid.iter().rev() {
-        let s: String = layer
-            .iter()
-            .map(|b| match b {
-                F => '@',
-                R => '#',
-                A => '.',
-            })
-            .collect();
-        println!("{}", s);
+// rows of the tower shown in each frame
+const FRAME_ROWS: usize = 30;
+
+fn draw(
+    buried: &[[Block; 9]],
+    grid: &[[Block; 9]],
+    rows: usize,
+    lines_past: u64,
+    taken: u64,
+) -> Frame {
+    // skip the air above the tower
+    let air = grid.iter().rev().take_while(|l| l[1..8] == [A; 7]).count();
+    let height = grid.len() - air;
+
+    let tower = buried.iter().chain(&grid[..height]);
+    let mut frame = Frame::new(9, rows.min(buried.len() + height));
+    for (y, layer) in tower.rev().take(rows).enumerate() {
+        for (x, b) in layer.iter().enumerate() {
+            let (ch, color) = match b {
+                F => ('@', Color::Yellow),
+                R if x == 0 || x == 8 => ('|', Color::Gray),
+                R => ('#', Color::White),
+                A => ('.', Color::Gray),
+            };
+            frame.set(x, y, ch, color);
+        }
     }
-    println!();
+    frame.set_caption(format!(
+        "pieces: {taken}, height: {}",
+        lines_past + height as u64 - 1
+    ));
+    frame
 }
 
 fn line_hash(line: &[Block; 9]) -> u8 {
@@ -103,7 +123,16 @@ fn grid_hash(grid: &[[Block; 9]], next_piece: usize, next_jet: usize) -> Vec<u8>
         .collect()
 }
 
-fn tetris(input: &str, pieces: u64) -> u64 {
+/// Drop `pieces` rocks and return the tower height. Observers get the top
+/// `frame_rows` rows of the tower each time a rock comes to rest, and if
+/// `tower` is given it's filled with every row, floor first.
+fn tetris<O: Observer>(
+    input: &str,
+    pieces: u64,
+    frame_rows: usize,
+    observer: &mut O,
+    tower: Option<&mut Vec<[Block; 9]>>,
+) -> u64 {
     let (_, jets) = jets(input).unwrap();
 
     // loop the jets forever
@@ -115,6 +144,9 @@ fn tetris(input: &str, pieces: u64) -> u64 {
     grid.push(FLOOR);
 
     let mut lines_past: u64 = 0;
+    // rows trimmed off the bottom, only kept for drawing
+    let keep_buried = O::ENABLED || tower.is_some();
+    let mut buried = Vec::new();
     let mut seen: BTreeMap<Vec<u8>, (u64, u64)> = BTreeMap::new();
 
     let mut pieces_cycle = PIECES.iter().enumerate().cycle().peekable();
@@ -261,9 +293,16 @@ fn tetris(input: &str, pieces: u64) -> u64 {
             }
         }
 
+        if O::ENABLED {
+            observer.frame(draw(&buried, &grid, frame_rows, lines_past, taken + 1));
+        }
+
         if let Some(mut new_floor) = grid.iter().skip(1).position(|l| *l == FLOOR) {
             // found another floor! increase the count to this index
             lines_past += new_floor as u64 + 1;
+            if keep_buried {
+                buried.extend_from_slice(&grid[..=new_floor]);
+            }
             // we can destroy the previous layers to save allocation size
             new_floor += 2;
             grid.retain(|_| {
@@ -295,16 +334,47 @@ fn tetris(input: &str, pieces: u64) -> u64 {
         }
     }
 
+    if let Some(tower) = tower {
+        tower.clear();
+        tower.extend(buried.iter().chain(&grid));
+    }
+
     // subtract 1 for the (first) floor
     lines_past + grid.len() as u64 - 1
 }
 
 fn part1(input: &str) -> u64 {
-    tetris(input, 2022)
+    tetris(input, 2022, 0, &mut Disabled, None)
 }
 
 fn part2(input: &str) -> u64 {
-    tetris(input, 1_000_000_000_000)
+    tetris(input, 1_000_000_000_000, 0, &mut Disabled, None)
+}
+
+/// Show the top of the tower as each piece comes to rest, as in part 1.
+pub fn visualize(input: &str, observer: &mut impl Observer) {
+    tetris(input, 2022, FRAME_ROWS, observer, None);
+}
+
+/// The whole part 1 tower, rock shaded by height.
+pub fn render(input: &str) -> Image {
+    let mut tower = Vec::new();
+    tetris(input, 2022, 0, &mut Disabled, Some(&mut tower));
+
+    let mut image = Image::new(9, tower.len(), Rgb::NAVY);
+    let top = tower.len().saturating_sub(1) as f64;
+    // the image's y grows down, the tower's up
+    for (y, layer) in tower.iter().rev().enumerate() {
+        for (x, b) in layer.iter().enumerate() {
+            let color = match b {
+                R if x == 0 || x == 8 => Rgb::GRAY,
+                R => heat(top - y as f64, -top / 4.0, top),
+                _ => Rgb::NAVY,
+            };
+            image.set(x, y, color);
+        }
+    }
+    image.scaled(3)
 }
 
 #[cfg(test)]
@@ -316,6 +386,25 @@ mod tests {
         assert_eq!(3068, part1(TEST_INPUT));
     }
 
+    #[test]
+    fn frames() {
+        let mut frames = Vec::new();
+        visualize(TEST_INPUT, &mut frames);
+        assert_eq!(2022, frames.len());
+        assert_eq!("|..####.|\n|#######|\n", frames[0].to_plain());
+        assert_eq!("pieces: 1, height: 1", frames[0].caption());
+        assert_eq!("pieces: 2022, height: 3068", frames[2021].caption());
+    }
+
+    #[test]
+    fn tower() {
+        let mut tower = Vec::new();
+        let height = tetris(TEST_INPUT, 2022, 0, &mut Disabled, Some(&mut tower));
+        assert_eq!(height + 1, tower.len() as u64);
+        assert_eq!([R; 9], tower[0]);
+        assert_eq!([R, A, A, R, R, R, R, A, R], tower[1]);
+    }
+
     #[test]
     fn test_part2() {
         assert_eq!(1514285714288, part2(TEST_INPUT));
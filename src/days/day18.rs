@@ -0,0 +1,118 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::{i32 as i32_parser, newline},
+    multi::separated_list0,
+    sequence::tuple,
+    IResult,
+};
+use std::collections::BTreeSet;
+
+pub fn solve(input: &str) -> String {
+    let p1 = part1(input);
+    let p2 = part2(input);
+    format!("{p1}, {p2}")
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+struct Cube {
+    x: i32,
+    y: i32,
+    z: i32,
+}
+
+impl Cube {
+    fn neighbors(self) -> [Cube; 6] {
+        let Cube { x, y, z } = self;
+        [
+            Cube { x: x - 1, y, z },
+            Cube { x: x + 1, y, z },
+            Cube { x, y: y - 1, z },
+            Cube { x, y: y + 1, z },
+            Cube { x, y, z: z - 1 },
+            Cube { x, y, z: z + 1 },
+        ]
+    }
+}
+
+fn cube(i: &str) -> IResult<&str, Cube> {
+    tuple((i32_parser, tag(","), i32_parser, tag(","), i32_parser))(i)
+        .map(|(r, (x, _, y, _, z))| (r, Cube { x, y, z }))
+}
+
+fn cubes(i: &str) -> IResult<&str, BTreeSet<Cube>> {
+    separated_list0(newline, cube)(i).map(|(r, cubes)| (r, cubes.into_iter().collect()))
+}
+
+fn part1(input: &str) -> usize {
+    let (_, cubes) = cubes(input).unwrap();
+    cubes
+        .iter()
+        .flat_map(|c| c.neighbors())
+        .filter(|n| !cubes.contains(n))
+        .count()
+}
+
+fn part2(input: &str) -> usize {
+    let (_, cubes) = cubes(input).unwrap();
+    if cubes.is_empty() {
+        return 0;
+    }
+
+    // bounding box with a layer of air all round, so the outside is connected
+    let min = |f: fn(&Cube) -> i32| cubes.iter().map(f).min().unwrap() - 1;
+    let max = |f: fn(&Cube) -> i32| cubes.iter().map(f).max().unwrap() + 1;
+    let (min_x, min_y, min_z) = (min(|c| c.x), min(|c| c.y), min(|c| c.z));
+    let (max_x, max_y, max_z) = (max(|c| c.x), max(|c| c.y), max(|c| c.z));
+    let in_bounds = |c: &Cube| {
+        (min_x..=max_x).contains(&c.x)
+            && (min_y..=max_y).contains(&c.y)
+            && (min_z..=max_z).contains(&c.z)
+    };
+
+    // flood fill the outside air, counting every face of lava it touches
+    let start = Cube {
+        x: min_x,
+        y: min_y,
+        z: min_z,
+    };
+    let mut outside = BTreeSet::from([start]);
+    let mut stack = vec![start];
+    let mut faces = 0;
+    while let Some(air) = stack.pop() {
+        for n in air.neighbors() {
+            if cubes.contains(&n) {
+                faces += 1;
+            } else if in_bounds(&n) && outside.insert(n) {
+                stack.push(n);
+            }
+        }
+    }
+
+    faces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        assert_eq!(10, part1("1,1,1\n2,1,1"));
+        assert_eq!(64, part1(TEST_INPUT));
+        assert_eq!(58, part2(TEST_INPUT));
+    }
+
+    const TEST_INPUT: &str = "2,2,2
+1,2,2
+3,2,2
+2,1,2
+2,3,2
+2,2,1
+2,2,3
+2,2,4
+2,2,6
+1,2,5
+3,2,5
+2,1,5
+2,3,5";
+}
@@ -0,0 +1,172 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::{newline, u32 as u32_parser},
+    multi::separated_list0,
+    sequence::{delimited, terminated, tuple},
+    IResult,
+};
+
+pub fn solve(input: &str) -> String {
+    let p1 = part1(input);
+    let p2 = part2(input);
+    format!("{p1}, {p2}")
+}
+
+// indexes into the resource and robot arrays
+const ORE: usize = 0;
+const CLAY: usize = 1;
+const OBSIDIAN: usize = 2;
+const GEODE: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Blueprint {
+    id: u32,
+    // costs[robot][resource]
+    costs: [[u32; 4]; 4],
+    // no point having more robots of a kind than we could spend in a minute
+    max_robots: [u32; 4],
+}
+
+impl Blueprint {
+    fn new(id: u32, ore: u32, clay: u32, obsidian: (u32, u32), geode: (u32, u32)) -> Self {
+        let costs = [
+            [ore, 0, 0, 0],
+            [clay, 0, 0, 0],
+            [obsidian.0, obsidian.1, 0, 0],
+            [geode.0, 0, geode.1, 0],
+        ];
+        let mut max_robots = [u32::MAX; 4];
+        for resource in [ORE, CLAY, OBSIDIAN] {
+            max_robots[resource] = costs.iter().map(|c| c[resource]).max().unwrap();
+        }
+        Self {
+            id,
+            costs,
+            max_robots,
+        }
+    }
+}
+
+fn blueprint(i: &str) -> IResult<&str, Blueprint> {
+    tuple((
+        delimited(tag("Blueprint "), u32_parser, tag(": ")),
+        delimited(tag("Each ore robot costs "), u32_parser, tag(" ore. ")),
+        delimited(tag("Each clay robot costs "), u32_parser, tag(" ore. ")),
+        tuple((
+            delimited(tag("Each obsidian robot costs "), u32_parser, tag(" ore and ")),
+            terminated(u32_parser, tag(" clay. ")),
+        )),
+        tuple((
+            delimited(tag("Each geode robot costs "), u32_parser, tag(" ore and ")),
+            terminated(u32_parser, tag(" obsidian.")),
+        )),
+    ))(i)
+    .map(|(r, (id, ore, clay, obsidian, geode))| (r, Blueprint::new(id, ore, clay, obsidian, geode)))
+}
+
+fn blueprints(i: &str) -> IResult<&str, Vec<Blueprint>> {
+    separated_list0(newline, blueprint)(i)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct State {
+    time_left: u32,
+    stock: [u32; 4],
+    robots: [u32; 4],
+}
+
+fn max_geodes(blueprint: &Blueprint, minutes: u32) -> u32 {
+    let start = State {
+        time_left: minutes,
+        stock: [0; 4],
+        robots: [1, 0, 0, 0],
+    };
+    let mut best = 0;
+    search(blueprint, start, &mut best);
+    best
+}
+
+// branch on which robot to build next, skipping ahead the minutes spent
+// saving up for it
+fn search(blueprint: &Blueprint, state: State, best: &mut u32) {
+    let t = state.time_left;
+
+    // geodes if we never build anything else
+    let idle = state.stock[GEODE] + state.robots[GEODE] * t;
+    *best = (*best).max(idle);
+
+    // even a new geode robot every remaining minute can't beat the best
+    if idle + t * t.saturating_sub(1) / 2 <= *best {
+        return;
+    }
+
+    // geode robots first, so good answers show up early and prune more
+    for robot in [GEODE, OBSIDIAN, CLAY, ORE] {
+        if state.robots[robot] >= blueprint.max_robots[robot] {
+            continue;
+        }
+
+        let cost = &blueprint.costs[robot];
+        let mut wait = Some(0);
+        for resource in [ORE, CLAY, OBSIDIAN] {
+            let short = cost[resource].saturating_sub(state.stock[resource]);
+            if short == 0 {
+                continue;
+            }
+            let rate = state.robots[resource];
+            wait = match wait {
+                Some(w) if rate > 0 => Some(w.max(short.div_ceil(rate))),
+                _ => None,
+            };
+        }
+        // a robot finished in the last minute never gets to collect anything
+        let Some(wait) = wait.filter(|w| w + 1 < t) else {
+            continue;
+        };
+
+        let mut next = state;
+        next.time_left = t - wait - 1;
+        for resource in [ORE, CLAY, OBSIDIAN, GEODE] {
+            next.stock[resource] += state.robots[resource] * (wait + 1);
+            next.stock[resource] -= cost[resource];
+        }
+        next.robots[robot] += 1;
+        search(blueprint, next, best);
+    }
+}
+
+// one thread per blueprint
+fn max_geodes_all(blueprints: &[Blueprint], minutes: u32) -> Vec<u32> {
+    let threads: Vec<_> = blueprints
+        .iter()
+        .map(|&bp| std::thread::spawn(move || max_geodes(&bp, minutes)))
+        .collect();
+    threads.into_iter().map(|t| t.join().unwrap()).collect()
+}
+
+fn part1(input: &str) -> u32 {
+    let (_, blueprints) = blueprints(input).unwrap();
+    let geodes = max_geodes_all(&blueprints, 24);
+    blueprints.iter().zip(geodes).map(|(bp, g)| bp.id * g).sum()
+}
+
+fn part2(input: &str) -> u32 {
+    let (_, mut blueprints) = blueprints(input).unwrap();
+    blueprints.truncate(3);
+    max_geodes_all(&blueprints, 32).into_iter().product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        assert_eq!(33, part1(TEST_INPUT));
+        let (_, blueprints) = blueprints(TEST_INPUT).unwrap();
+        assert_eq!(vec![56, 62], max_geodes_all(&blueprints, 32));
+    }
+
+    const TEST_INPUT: &str = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
+Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.";
+}
@@ -0,0 +1,161 @@
+pub fn solve(input: &str) -> String {
+    let p1 = part1(input);
+    let p2 = part2(input);
+    format!("{p1}, {p2}")
+}
+
+const DECRYPTION_KEY: i64 = 811589153;
+
+fn numbers(input: &str) -> Vec<i64> {
+    input.lines().map(|l| l.trim().parse().unwrap()).collect()
+}
+
+/// The circle as a list of buckets of about sqrt(n) ids each, so finding,
+/// removing and inserting an element are all O(sqrt(n)) instead of the O(n)
+/// of shifting one big `Vec`.
+///
+/// Elements are ids into the original list, so duplicate values don't matter.
+struct Ring {
+    buckets: Vec<Vec<usize>>,
+    // which bucket each id is in
+    bucket_of: Vec<usize>,
+    bucket_size: usize,
+}
+
+impl Ring {
+    fn new(len: usize) -> Self {
+        let bucket_size = ((len as f64).sqrt() as usize).max(1);
+        let mut ring = Self {
+            buckets: vec![(0..len).collect()],
+            bucket_of: vec![0; len],
+            bucket_size,
+        };
+        ring.rebuild();
+        ring
+    }
+
+    // split everything back into evenly sized buckets
+    fn rebuild(&mut self) {
+        let ids: Vec<usize> = self.buckets.concat();
+        self.buckets = ids.chunks(self.bucket_size).map(<[usize]>::to_vec).collect();
+        for (b, bucket) in self.buckets.iter().enumerate() {
+            for &id in bucket {
+                self.bucket_of[id] = b;
+            }
+        }
+    }
+
+    fn remove(&mut self, id: usize) -> usize {
+        let b = self.bucket_of[id];
+        let before: usize = self.buckets[..b].iter().map(Vec::len).sum();
+        let bucket = &mut self.buckets[b];
+        let index = bucket.iter().position(|&i| i == id).unwrap();
+        bucket.remove(index);
+        before + index
+    }
+
+    fn insert(&mut self, mut position: usize, id: usize) {
+        let last = self.buckets.len() - 1;
+        for (b, bucket) in self.buckets.iter_mut().enumerate() {
+            if position <= bucket.len() || b == last {
+                bucket.insert(position.min(bucket.len()), id);
+                self.bucket_of[id] = b;
+                if bucket.len() > 2 * self.bucket_size {
+                    self.rebuild();
+                }
+                return;
+            }
+            position -= bucket.len();
+        }
+    }
+
+    fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.buckets.iter().flatten().copied()
+    }
+}
+
+fn mix(numbers: &[i64], rounds: usize) -> Vec<i64> {
+    let len = numbers.len();
+    // nowhere to move to
+    if len <= 1 {
+        return numbers.to_vec();
+    }
+    let mut ring = Ring::new(len);
+    for _ in 0..rounds {
+        for (id, &n) in numbers.iter().enumerate() {
+            let from = ring.remove(id);
+            // moving around the other len - 1 elements
+            let to = (from as i64 + n).rem_euclid(len as i64 - 1);
+            ring.insert(to as usize, id);
+        }
+    }
+    ring.ids().map(|id| numbers[id]).collect()
+}
+
+/// The numbers 1000, 2000 and 3000 after the 0.
+fn grove_coordinates(mixed: &[i64]) -> [i64; 3] {
+    let zero = mixed.iter().position(|&n| n == 0).unwrap();
+    [1000, 2000, 3000].map(|offset| mixed[(zero + offset) % mixed.len()])
+}
+
+fn part1(input: &str) -> i64 {
+    grove_coordinates(&mix(&numbers(input), 1)).iter().sum()
+}
+
+fn part2(input: &str) -> i64 {
+    let numbers: Vec<i64> = numbers(input)
+        .into_iter()
+        .map(|n| n * DECRYPTION_KEY)
+        .collect();
+    grove_coordinates(&mix(&numbers, 10)).iter().sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        let mixed = mix(&numbers(TEST_INPUT), 1);
+        let zero = mixed.iter().position(|&n| n == 0).unwrap();
+        let around: Vec<i64> = (0..7).map(|i| mixed[(zero + i) % 7]).collect();
+        assert_eq!(vec![0, 3, -2, 1, 2, -3, 4], around);
+        assert_eq!([4, -3, 2], grove_coordinates(&mixed));
+
+        assert_eq!(3, part1(TEST_INPUT));
+        assert_eq!(1623178306, part2(TEST_INPUT));
+    }
+
+    #[test]
+    fn matches_vec() {
+        // the straightforward O(n^2) mix, on enough numbers to need a few
+        // buckets and rebuilds, with lots of repeats
+        let numbers: Vec<i64> = (0..300_i64).map(|i| (i * 7919) % 41 - 20).collect();
+        let mut ids: Vec<usize> = (0..numbers.len()).collect();
+        for (id, &n) in numbers.iter().enumerate() {
+            let from = ids.iter().position(|&i| i == id).unwrap();
+            ids.remove(from);
+            let to = (from as i64 + n).rem_euclid(numbers.len() as i64 - 1);
+            ids.insert(to as usize, id);
+        }
+        let expected: Vec<i64> = ids.iter().map(|&id| numbers[id]).collect();
+
+        assert_eq!(expected, mix(&numbers, 1));
+    }
+
+    #[test]
+    fn tiny() {
+        assert_eq!(Vec::<i64>::new(), mix(&[], 1));
+        assert_eq!(vec![5], mix(&[5], 3));
+        assert_eq!(0, part1("0"));
+    }
+
+    const TEST_INPUT: &str = "1
+2
+-3
+3
+-2
+0
+4
+";
+}
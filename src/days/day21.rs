@@ -1,12 +1,13 @@
+use crate::expr::{self, Expr};
 use nom::{
-    branch::alt,
     bytes::complete::tag,
-    character::complete::{alpha1, digit1},
-    combinator::map,
+    character::complete::{alpha1, newline, not_line_ending},
+    combinator::{map, map_res},
     multi::separated_list0,
     sequence::separated_pair,
     IResult,
 };
+use std::collections::HashMap;
 
 pub fn solve(input: &str) -> String {
     let p1 = part1(input);
@@ -14,204 +15,56 @@ pub fn solve(input: &str) -> String {
     format!("{p1}, {p2}")
 }
 
-#[derive(Debug, Clone, Copy)]
-enum AstNode<'m> {
-    Immediate(i64),
-    Add(&'m str, &'m str),
-    Sub(&'m str, &'m str),
-    Mul(&'m str, &'m str),
-    Div(&'m str, &'m str),
-}
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 struct Monkey<'m> {
     name: &'m str,
-    node: AstNode<'m>,
+    // what the monkey yells, in terms of other monkeys' names
+    job: Expr,
 }
 
-fn monkeys(s: &str) -> IResult<&str, Vec<Monkey>> {
+fn monkeys(s: &str) -> IResult<&str, Vec<Monkey<'_>>> {
     separated_list0(
-        nom::character::complete::newline,
-        map(separated_pair(alpha1, tag(": "), node), |(name, node)| {
-            Monkey { name, node }
-        }),
+        newline,
+        map(
+            separated_pair(alpha1, tag(": "), map_res(not_line_ending, str::parse)),
+            |(name, job)| Monkey { name, job },
+        ),
     )(s)
 }
 
-fn node(s: &str) -> IResult<&str, AstNode> {
-    alt((
-        map(separated_pair(alpha1, tag(" + "), alpha1), |(a, b)| {
-            AstNode::Add(a, b)
-        }),
-        map(separated_pair(alpha1, tag(" - "), alpha1), |(a, b)| {
-            AstNode::Sub(a, b)
-        }),
-        map(separated_pair(alpha1, tag(" * "), alpha1), |(a, b)| {
-            AstNode::Mul(a, b)
-        }),
-        map(separated_pair(alpha1, tag(" / "), alpha1), |(a, b)| {
-            AstNode::Div(a, b)
-        }),
-        map(digit1, |d: &str| AstNode::Immediate(d.parse().unwrap())),
-    ))(s)
-}
-
-fn find_monkey<'a>(monkeys: &'a [Monkey], name: &str) -> Monkey<'a> {
-    *monkeys.iter().find(|m| m.name == name).unwrap()
-}
-
-fn eval(monkeys: &[Monkey], name: &str) -> i64 {
-    let node = find_monkey(monkeys, name).node;
-    match node {
-        AstNode::Immediate(i) => i,
-        AstNode::Add(a, b) => eval(monkeys, a) + eval(monkeys, b),
-        AstNode::Sub(a, b) => eval(monkeys, a) - eval(monkeys, b),
-        AstNode::Mul(a, b) => eval(monkeys, a) * eval(monkeys, b),
-        AstNode::Div(a, b) => eval(monkeys, a) / eval(monkeys, b),
-    }
-}
-
-#[derive(Debug, Clone)]
-enum SymbolicExpr {
-    Symbolic,
-    Concrete(i64),
-    Add(Box<Self>, Box<Self>),
-    Sub(Box<Self>, Box<Self>),
-    Mul(Box<Self>, Box<Self>),
-    Div(Box<Self>, Box<Self>),
+fn jobs(input: &str) -> HashMap<&str, Expr> {
+    let monkeys = monkeys(input).unwrap().1;
+    monkeys.into_iter().map(|m| (m.name, m.job)).collect()
 }
 
-impl SymbolicExpr {
-    fn reduce(self) -> Self {
-        use SymbolicExpr::*;
-
-        match self {
-            Symbolic => self,
-            Concrete(_) => self,
-            Add(a, b) => {
-                let a = a.reduce();
-                let b = b.reduce();
-                if let (&Concrete(a), &Concrete(b)) = (&a, &b) {
-                    Concrete(a + b)
-                } else {
-                    Add(Box::new(a), Box::new(b))
-                }
-            }
-            Sub(a, b) => {
-                let a = a.reduce();
-                let b = b.reduce();
-                if let (&Concrete(a), &Concrete(b)) = (&a, &b) {
-                    Concrete(a - b)
-                } else {
-                    Sub(Box::new(a), Box::new(b))
-                }
-            }
-            Mul(a, b) => {
-                let a = a.reduce();
-                let b = b.reduce();
-                if let (&Concrete(a), &Concrete(b)) = (&a, &b) {
-                    Concrete(a * b)
-                } else {
-                    Mul(Box::new(a), Box::new(b))
-                }
-            }
-            Div(a, b) => {
-                let a = a.reduce();
-                let b = b.reduce();
-                if let (&Concrete(a), &Concrete(b)) = (&a, &b) {
-                    Concrete(a / b)
-                } else {
-                    Div(Box::new(a), Box::new(b))
-                }
-            }
-        }
-    }
+fn eval(jobs: &HashMap<&str, Expr>, name: &str) -> i64 {
+    jobs[name]
+        .eval(&|other: &str| Some(eval(jobs, other)))
+        .unwrap()
 }
 
-fn build_expr(monkeys: &[Monkey], name: &str) -> SymbolicExpr {
-    use SymbolicExpr::*;
+// inline every monkey's job into one big expression, leaving `humn` symbolic
+fn build_expr(jobs: &HashMap<&str, Expr>, name: &str) -> Expr {
     if name == "humn" {
-        return Symbolic;
+        return Expr::var(name);
     }
 
-    let node = find_monkey(monkeys, name).node;
-    match node {
-        AstNode::Immediate(i) => Concrete(i),
-        AstNode::Add(a, b) => Add(
-            Box::new(build_expr(monkeys, a)),
-            Box::new(build_expr(monkeys, b)),
-        ),
-        AstNode::Sub(a, b) => Sub(
-            Box::new(build_expr(monkeys, a)),
-            Box::new(build_expr(monkeys, b)),
-        ),
-        AstNode::Mul(a, b) => Mul(
-            Box::new(build_expr(monkeys, a)),
-            Box::new(build_expr(monkeys, b)),
-        ),
-        AstNode::Div(a, b) => Div(
-            Box::new(build_expr(monkeys, a)),
-            Box::new(build_expr(monkeys, b)),
-        ),
-    }
-}
-
-fn balance_expr(expr: SymbolicExpr, target: i64) -> i64 {
-    use SymbolicExpr::*;
-    let (expr, target) = match expr {
-        Symbolic => return target,
-        Add(a, b) => match (*a, *b) {
-            (expr, Concrete(concrete)) => (expr, target - concrete),
-            (Concrete(concrete), expr) => (expr, target - concrete),
-            _ => panic!("one side of expression needs to be concrete"),
-        },
-        Mul(a, b) => match (*a, *b) {
-            (expr, Concrete(concrete)) => (expr, target / concrete),
-            (Concrete(concrete), expr) => (expr, target / concrete),
-            _ => panic!("one side of expression needs to be concrete"),
-        },
-        Sub(a, b) => match (*a, *b) {
-            (expr, Concrete(concrete)) => (expr, target + concrete),
-            (Concrete(concrete), expr) => (expr, concrete - target),
-            _ => panic!("one side of expression needs to be concrete"),
-        },
-        Div(a, b) => match (*a, *b) {
-            (expr, Concrete(concrete)) => (expr, target * concrete),
-            (Concrete(concrete), expr) => (expr, concrete / target),
-            _ => panic!("one side of expression needs to be concrete"),
-        },
-        Concrete(_) => panic!(),
-    };
-
-    balance_expr(expr, target)
-}
-
-fn solve_expr(left: SymbolicExpr, right: SymbolicExpr) -> i64 {
-    use SymbolicExpr::*;
-    // reduce the left and right sides
-    let left = left.reduce();
-    let right = right.reduce();
-
-    let (expr, concrete) = match (left, right) {
-        (expr, Concrete(concrete)) => (expr, concrete),
-        (Concrete(concrete), expr) => (expr, concrete),
-        _ => panic!("one side of expression needs to be concrete"),
-    };
-
-    balance_expr(expr, concrete)
+    jobs[name]
+        .substitute(&mut |other| Some(build_expr(jobs, other)))
+        .fold()
 }
 
 fn part1(input: &str) -> i64 {
-    let monkeys = monkeys(input).unwrap().1;
-    eval(&monkeys, "root")
+    let jobs = jobs(input);
+    eval(&jobs, "root")
 }
 
 fn part2(input: &str) -> i64 {
-    let monkeys = monkeys(input).unwrap().1;
+    let jobs = jobs(input);
 
-    let root = find_monkey(&monkeys, "root");
-    let AstNode::Add(a, b) = root.node else { panic!() };
-    solve_expr(build_expr(&monkeys, a), build_expr(&monkeys, b))
+    let Expr::Bin(_, a, b) = &jobs["root"] else { panic!() };
+    let (Expr::Var(a), Expr::Var(b)) = (&**a, &**b) else { panic!() };
+    expr::solve(&build_expr(&jobs, a), &build_expr(&jobs, b), "humn").unwrap()
 }
 
 #[cfg(test)]
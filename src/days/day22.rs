@@ -0,0 +1,303 @@
+use nom::{
+    branch::alt,
+    character::complete::{one_of, u32 as u32_parser},
+    combinator::map,
+    multi::many1,
+    IResult,
+};
+
+pub fn solve(input: &str) -> String {
+    let p1 = part1(input);
+    let p2 = part2(input);
+    format!("{p1}, {p2}")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Forward(u32),
+    Left,
+    Right,
+}
+
+fn path(i: &str) -> IResult<&str, Vec<Step>> {
+    many1(alt((
+        map(u32_parser, Step::Forward),
+        map(one_of("LR"), |c| if c == 'L' { Step::Left } else { Step::Right }),
+    )))(i)
+}
+
+// facings in password order: right, down, left, up
+const DIRS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pos {
+    x: i32,
+    y: i32,
+    facing: usize,
+}
+
+impl Pos {
+    fn password(&self) -> i32 {
+        1000 * (self.y + 1) + 4 * (self.x + 1) + self.facing as i32
+    }
+}
+
+struct Map<'a> {
+    rows: Vec<&'a [u8]>,
+}
+
+impl<'a> Map<'a> {
+    // anything off the map is a space
+    fn get(&self, x: i32, y: i32) -> u8 {
+        if x < 0 || y < 0 {
+            return b' ';
+        }
+        let row = self.rows.get(y as usize).copied().unwrap_or_default();
+        row.get(x as usize).copied().unwrap_or(b' ')
+    }
+}
+
+fn parse(input: &str) -> (Map<'_>, Vec<Step>) {
+    let (map, steps) = input.split_once("\n\n").unwrap();
+    let rows = map.lines().map(str::as_bytes).collect();
+    let (_, steps) = path(steps.trim()).unwrap();
+    (Map { rows }, steps)
+}
+
+// follow the path, calling `wrap` to find where stepping off the map lands
+fn walk(map: &Map, steps: &[Step], wrap: impl Fn(Pos) -> Pos) -> Pos {
+    let x = map.rows[0].iter().position(|&b| b == b'.').unwrap() as i32;
+    let mut pos = Pos { x, y: 0, facing: 0 };
+
+    for step in steps {
+        match step {
+            Step::Left => pos.facing = (pos.facing + 3) % 4,
+            Step::Right => pos.facing = (pos.facing + 1) % 4,
+            Step::Forward(n) => {
+                for _ in 0..*n {
+                    let (dx, dy) = DIRS[pos.facing];
+                    let mut next = Pos {
+                        x: pos.x + dx,
+                        y: pos.y + dy,
+                        ..pos
+                    };
+                    if map.get(next.x, next.y) == b' ' {
+                        next = wrap(pos);
+                    }
+                    if map.get(next.x, next.y) == b'#' {
+                        break;
+                    }
+                    pos = next;
+                }
+            }
+        }
+    }
+
+    pos
+}
+
+fn part1(input: &str) -> i32 {
+    let (map, steps) = parse(input);
+    let end = walk(&map, &steps, |pos| {
+        // walk backwards to the far edge of the row or column
+        let (dx, dy) = DIRS[pos.facing];
+        let mut back = pos;
+        while map.get(back.x - dx, back.y - dy) != b' ' {
+            back.x -= dx;
+            back.y -= dy;
+        }
+        back
+    });
+    end.password()
+}
+
+type V3 = [i32; 3];
+
+fn dot(a: V3, b: V3) -> i32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn neg(a: V3) -> V3 {
+    a.map(|v| -v)
+}
+
+fn add(a: V3, b: V3) -> V3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: V3, k: i32) -> V3 {
+    a.map(|v| v * k)
+}
+
+/// Where one face of the net ends up once it's folded: `normal` points out of
+/// the cube, and `right`/`down` are where the map's +x and +y point.
+#[derive(Debug, Clone, Copy)]
+struct Face {
+    x: i32,
+    y: i32,
+    normal: V3,
+    right: V3,
+    down: V3,
+}
+
+struct Cube {
+    size: i32,
+    faces: Vec<Face>,
+}
+
+impl Cube {
+    /// Fold the net up by walking from face to face, tipping each neighbour
+    /// over the shared edge.
+    fn fold(map: &Map) -> Cube {
+        let tiles = map.rows.iter().flat_map(|r| r.iter()).filter(|&&b| b != b' ');
+        let size = ((tiles.count() / 6) as f64).sqrt() as i32;
+
+        let first = Face {
+            x: map.rows[0].iter().position(|&b| b != b' ').unwrap() as i32 / size,
+            y: 0,
+            normal: [0, 0, -1],
+            right: [1, 0, 0],
+            down: [0, 1, 0],
+        };
+        let mut faces = vec![first];
+        let mut stack = vec![first];
+        while let Some(f) = stack.pop() {
+            let neighbours = [
+                (f.x + 1, f.y, f.right, neg(f.normal), f.down),
+                (f.x - 1, f.y, neg(f.right), f.normal, f.down),
+                (f.x, f.y + 1, f.down, f.right, neg(f.normal)),
+                (f.x, f.y - 1, neg(f.down), f.right, f.normal),
+            ];
+            for (x, y, normal, right, down) in neighbours {
+                let on_map = map.get(x * size, y * size) != b' ';
+                if on_map && !faces.iter().any(|f| (f.x, f.y) == (x, y)) {
+                    let face = Face {
+                        x,
+                        y,
+                        normal,
+                        right,
+                        down,
+                    };
+                    faces.push(face);
+                    stack.push(face);
+                }
+            }
+        }
+        assert_eq!(6, faces.len(), "map isn't a cube net");
+
+        Cube { size, faces }
+    }
+
+    fn face_at(&self, x: i32, y: i32) -> &Face {
+        let (fx, fy) = (x.div_euclid(self.size), y.div_euclid(self.size));
+        self.faces.iter().find(|f| (f.x, f.y) == (fx, fy)).unwrap()
+    }
+
+    /// Step over the edge of a face onto the next one round the cube.
+    ///
+    /// Tile centres sit on the surface of a cube of side `2 * size` centred
+    /// on the origin, so stepping over an edge moves one unit along the
+    /// old direction and one unit back along the old normal.
+    fn wrap(&self, pos: Pos) -> Pos {
+        let n = self.size;
+        let from = self.face_at(pos.x, pos.y);
+        let (dx, dy) = DIRS[pos.facing];
+        let dir = add(scale(from.right, dx), scale(from.down, dy));
+
+        let (i, j) = (pos.x - from.x * n, pos.y - from.y * n);
+        let mut point = scale(from.normal, n);
+        point = add(point, scale(from.right, 2 * i - n + 1));
+        point = add(point, scale(from.down, 2 * j - n + 1));
+        point = add(add(point, dir), neg(from.normal));
+
+        let to = self.faces.iter().find(|f| f.normal == dir).unwrap();
+        let on_face = add(point, scale(to.normal, -n));
+        let i = (dot(on_face, to.right) + n - 1) / 2;
+        let j = (dot(on_face, to.down) + n - 1) / 2;
+        let new_dir = neg(from.normal);
+        let facing = (dot(new_dir, to.right), dot(new_dir, to.down));
+
+        Pos {
+            x: to.x * n + i,
+            y: to.y * n + j,
+            facing: DIRS.iter().position(|&d| d == facing).unwrap(),
+        }
+    }
+}
+
+fn part2(input: &str) -> i32 {
+    let (map, steps) = parse(input);
+    let cube = Cube::fold(&map);
+    walk(&map, &steps, |pos| cube.wrap(pos)).password()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        assert_eq!(6032, part1(TEST_INPUT));
+        assert_eq!(5031, part2(TEST_INPUT));
+    }
+
+    // stepping off any edge and straight back lands where we started
+    fn assert_round_trips(input: &str) {
+        let (map, _) = parse(input);
+        let cube = Cube::fold(&map);
+        for (y, row) in map.rows.iter().enumerate() {
+            for x in 0..row.len() {
+                let (x, y) = (x as i32, y as i32);
+                if map.get(x, y) == b' ' {
+                    continue;
+                }
+                for (facing, (dx, dy)) in DIRS.iter().enumerate() {
+                    if map.get(x + dx, y + dy) != b' ' {
+                        continue;
+                    }
+                    let over = cube.wrap(Pos { x, y, facing });
+                    assert_ne!(b' ', map.get(over.x, over.y));
+                    let back = cube.wrap(Pos {
+                        facing: (over.facing + 2) % 4,
+                        ..over
+                    });
+                    assert_eq!((x, y, facing), (back.x, back.y, (back.facing + 2) % 4));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wrap_round_trips() {
+        assert_round_trips(TEST_INPUT);
+        // the other common net shape
+        assert_round_trips(
+            "  ....
+  ....
+  ..
+  ..
+....
+....
+..
+..
+
+1",
+        );
+    }
+
+    const TEST_INPUT: &str = "        ...#
+        .#..
+        #...
+        ....
+...#.......#
+........#...
+..#....#....
+..........#.
+        ...#....
+        .....#..
+        .#......
+        ......#.
+
+10R5L5R10L4R5L5
+";
+}
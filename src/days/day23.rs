@@ -0,0 +1,123 @@
+use std::collections::{HashMap, HashSet};
+
+pub fn solve(input: &str) -> String {
+    let p1 = part1(input);
+    let p2 = part2(input);
+    format!("{p1}, {p2}")
+}
+
+type Elf = (i32, i32);
+
+fn elves(input: &str) -> HashSet<Elf> {
+    let mut elves = HashSet::new();
+    for (y, line) in input.lines().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            if c == '#' {
+                elves.insert((x as i32, y as i32));
+            }
+        }
+    }
+    elves
+}
+
+// north, south, west, east: the move and the three cells that must be empty
+const DIRECTIONS: [(Elf, [Elf; 3]); 4] = [
+    ((0, -1), [(-1, -1), (0, -1), (1, -1)]),
+    ((0, 1), [(-1, 1), (0, 1), (1, 1)]),
+    ((-1, 0), [(-1, -1), (-1, 0), (-1, 1)]),
+    ((1, 0), [(1, -1), (1, 0), (1, 1)]),
+];
+
+const NEIGHBOURS: [Elf; 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+// run one round, returning whether any elf moved
+fn round(elves: &mut HashSet<Elf>, round: usize) -> bool {
+    let free = |(x, y): Elf, (dx, dy): Elf| !elves.contains(&(x + dx, y + dy));
+
+    // proposed destination -> the elves that want it
+    let mut proposals: HashMap<Elf, Vec<Elf>> = HashMap::new();
+    for &elf in elves.iter() {
+        if NEIGHBOURS.iter().all(|&n| free(elf, n)) {
+            continue;
+        }
+        let choice = (0..4)
+            .map(|i| DIRECTIONS[(round + i) % 4])
+            .find(|(_, checks)| checks.iter().all(|&c| free(elf, c)));
+        if let Some(((dx, dy), _)) = choice {
+            proposals.entry((elf.0 + dx, elf.1 + dy)).or_default().push(elf);
+        }
+    }
+
+    // only the elves with a spot to themselves move
+    let moves: Vec<(Elf, Elf)> = proposals
+        .into_iter()
+        .filter_map(|(to, from)| match from[..] {
+            [from] => Some((from, to)),
+            _ => None,
+        })
+        .collect();
+    for (from, _) in &moves {
+        elves.remove(from);
+    }
+    elves.extend(moves.iter().map(|&(_, to)| to));
+    !moves.is_empty()
+}
+
+fn empty_ground(elves: &HashSet<Elf>) -> usize {
+    let (min_x, max_x) = (elves.iter().map(|e| e.0).min(), elves.iter().map(|e| e.0).max());
+    let (min_y, max_y) = (elves.iter().map(|e| e.1).min(), elves.iter().map(|e| e.1).max());
+    let width = (max_x.unwrap() - min_x.unwrap() + 1) as usize;
+    let height = (max_y.unwrap() - min_y.unwrap() + 1) as usize;
+    width * height - elves.len()
+}
+
+fn part1(input: &str) -> usize {
+    let mut elves = elves(input);
+    for r in 0..10 {
+        round(&mut elves, r);
+    }
+    empty_ground(&elves)
+}
+
+fn part2(input: &str) -> usize {
+    let mut elves = elves(input);
+    (0..).find(|&r| !round(&mut elves, r)).unwrap() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        assert_eq!(110, part1(TEST_INPUT));
+        assert_eq!(20, part2(TEST_INPUT));
+    }
+
+    #[test]
+    fn small() {
+        let mut elves = elves(".....\n..##.\n..#..\n.....\n..##.\n.....");
+        for r in 0..3 {
+            round(&mut elves, r);
+        }
+        assert_eq!(elves, super::elves("..#..\n....#\n#....\n....#\n.....\n..#.."));
+    }
+
+    const TEST_INPUT: &str = "....#..
+..###.#
+#...#.#
+.#...##
+#.###..
+##.#.##
+.#..#..
+";
+}
@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+
+pub fn solve(input: &str) -> String {
+    let p1 = part1(input);
+    let p2 = part2(input);
+    format!("{p1}, {p2}")
+}
+
+/// The valley inside the walls. Blizzards just wrap around, so rather than
+/// simulating them we check whether one would be over a cell at a given time.
+struct Valley {
+    rows: Vec<Vec<u8>>,
+    width: i32,
+    height: i32,
+}
+
+type Pos = (i32, i32);
+
+impl Valley {
+    fn parse(input: &str) -> Self {
+        let lines: Vec<&str> = input.lines().collect();
+        // drop the walls all round
+        let rows: Vec<Vec<u8>> = lines[1..lines.len() - 1]
+            .iter()
+            .map(|l| l.as_bytes()[1..l.len() - 1].to_vec())
+            .collect();
+        let width = rows[0].len() as i32;
+        let height = rows.len() as i32;
+        Self {
+            rows,
+            width,
+            height,
+        }
+    }
+
+    fn start(&self) -> Pos {
+        (0, -1)
+    }
+
+    fn goal(&self) -> Pos {
+        (self.width - 1, self.height)
+    }
+
+    fn at(&self, x: i32, y: i32) -> u8 {
+        self.rows[y.rem_euclid(self.height) as usize][x.rem_euclid(self.width) as usize]
+    }
+
+    fn open(&self, (x, y): Pos, time: i32) -> bool {
+        if (x, y) == self.start() || (x, y) == self.goal() {
+            return true;
+        }
+        if !(0..self.width).contains(&x) || !(0..self.height).contains(&y) {
+            return false;
+        }
+        // look back along each line for a blizzard that would be here now
+        self.at(x - time, y) != b'>'
+            && self.at(x + time, y) != b'<'
+            && self.at(x, y - time) != b'v'
+            && self.at(x, y + time) != b'^'
+    }
+
+    /// Breadth first search through (position, time), one minute at a time,
+    /// returning the time we reach `to`.
+    fn cross(&self, from: Pos, to: Pos, start_time: i32) -> i32 {
+        let mut frontier = HashSet::from([from]);
+        let mut time = start_time;
+        while !frontier.contains(&to) {
+            time += 1;
+            frontier = frontier
+                .iter()
+                .flat_map(|&(x, y)| [(x, y), (x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)])
+                .filter(|&p| self.open(p, time))
+                .collect();
+            assert!(!frontier.is_empty(), "trapped by blizzards");
+        }
+        time
+    }
+}
+
+fn part1(input: &str) -> i32 {
+    let valley = Valley::parse(input);
+    valley.cross(valley.start(), valley.goal(), 0)
+}
+
+fn part2(input: &str) -> i32 {
+    let valley = Valley::parse(input);
+    let (start, goal) = (valley.start(), valley.goal());
+    let there = valley.cross(start, goal, 0);
+    let back = valley.cross(goal, start, there);
+    valley.cross(start, goal, back)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        assert_eq!(18, part1(TEST_INPUT));
+        assert_eq!(54, part2(TEST_INPUT));
+    }
+
+    const TEST_INPUT: &str = "#.######
+#>>.<^<#
+#.<..<<#
+#>v.><>#
+#<^v^^>#
+######.#
+";
+}
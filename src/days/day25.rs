@@ -0,0 +1,105 @@
+use std::fmt;
+use std::str::FromStr;
+
+// there's no part 2 on the last day
+pub fn solve(input: &str) -> String {
+    part1(input)
+}
+
+/// A number in balanced base 5, with digits `=`, `-`, `0`, `1`, `2` for -2 to 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Snafu(i64);
+
+impl FromStr for Snafu {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(());
+        }
+        let mut n: i64 = 0;
+        for c in s.chars() {
+            let digit = match c {
+                '=' => -2,
+                '-' => -1,
+                '0' => 0,
+                '1' => 1,
+                '2' => 2,
+                _ => return Err(()),
+            };
+            n = n.checked_mul(5).and_then(|n| n.checked_add(digit)).ok_or(())?;
+        }
+        Ok(Snafu(n))
+    }
+}
+
+impl fmt::Display for Snafu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 == 0 {
+            return write!(f, "0");
+        }
+        let mut digits = Vec::new();
+        let mut n = self.0;
+        while n != 0 {
+            // pick the digit in -2..=2 that leaves a multiple of 5
+            let digit = (n + 2).rem_euclid(5) - 2;
+            digits.push(b"=-012"[(digit + 2) as usize] as char);
+            n = (n - digit) / 5;
+        }
+        let s: String = digits.iter().rev().collect();
+        write!(f, "{s}")
+    }
+}
+
+fn part1(input: &str) -> String {
+    let sum = input
+        .lines()
+        .map(|l| l.trim().parse::<Snafu>().unwrap().0)
+        .sum();
+    Snafu(sum).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests() {
+        assert_eq!("2=-1=0", part1(TEST_INPUT));
+    }
+
+    #[test]
+    fn conversions() {
+        for (n, s) in [
+            (1, "1"),
+            (3, "1="),
+            (8, "2="),
+            (10, "20"),
+            (20, "1-0"),
+            (2022, "1=11-2"),
+            (12345, "1-0---0"),
+            (314159265, "1121-1110-1=0"),
+            (-3, "-2"),
+            (0, "0"),
+        ] {
+            assert_eq!(s, Snafu(n).to_string());
+            assert_eq!(Ok(Snafu(n)), s.parse());
+        }
+        assert_eq!(Err(()), "3".parse::<Snafu>());
+    }
+
+    const TEST_INPUT: &str = "1=-0-2
+12111
+2=0=
+21
+2=01
+111
+20012
+112
+1=-1=
+1-12
+12
+1=
+122
+";
+}
@@ -0,0 +1,573 @@
+//! Small infix arithmetic language over named variables.
+//!
+//! Used by the days that carry their own little expression languages (monkey
+//! operations in day 11, monkey jobs in day 21).
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Num(i64),
+    Var(String),
+    Neg(Box<Expr>),
+    Bin(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Op {
+    fn precedence(self) -> u8 {
+        match self {
+            Op::Add | Op::Sub => 1,
+            Op::Mul | Op::Div => 2,
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => '*',
+            Op::Div => '/',
+        }
+    }
+
+    pub fn apply(self, a: i64, b: i64) -> Result<i64, EvalError> {
+        match self {
+            Op::Add => a.checked_add(b).ok_or(EvalError::Overflow),
+            Op::Sub => a.checked_sub(b).ok_or(EvalError::Overflow),
+            Op::Mul => a.checked_mul(b).ok_or(EvalError::Overflow),
+            Op::Div if b == 0 => Err(EvalError::DivisionByZero),
+            Op::Div => a.checked_div(b).ok_or(EvalError::Overflow),
+        }
+    }
+}
+
+/// Variable bindings used by [`Expr::eval`].
+pub trait Env {
+    fn get(&self, name: &str) -> Option<i64>;
+}
+
+impl<F: Fn(&str) -> Option<i64>> Env for F {
+    fn get(&self, name: &str) -> Option<i64> {
+        self(name)
+    }
+}
+
+impl<K: Borrow<str> + Hash + Eq> Env for HashMap<K, i64> {
+    fn get(&self, name: &str) -> Option<i64> {
+        HashMap::get(self, name).copied()
+    }
+}
+
+// a single binding, like `("old", 79)`
+impl Env for (&str, i64) {
+    fn get(&self, name: &str) -> Option<i64> {
+        (self.0 == name).then_some(self.1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    Unbound(String),
+    DivisionByZero,
+    Overflow,
+    // division doesn't carry over to remainders, see `Expr::eval_mod`
+    ModularDivision,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::Unbound(name) => write!(f, "unbound variable `{name}`"),
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+            EvalError::Overflow => write!(f, "arithmetic overflow"),
+            EvalError::ModularDivision => write!(f, "can't divide modulo a number"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+impl Expr {
+    pub fn var(name: impl Into<String>) -> Self {
+        Expr::Var(name.into())
+    }
+
+    pub fn bin(op: Op, a: Expr, b: Expr) -> Self {
+        Expr::Bin(op, Box::new(a), Box::new(b))
+    }
+
+    pub fn eval(&self, env: &impl Env) -> Result<i64, EvalError> {
+        match self {
+            Expr::Num(n) => Ok(*n),
+            Expr::Var(name) => env
+                .get(name)
+                .ok_or_else(|| EvalError::Unbound(name.clone())),
+            Expr::Neg(e) => e.eval(env)?.checked_neg().ok_or(EvalError::Overflow),
+            Expr::Bin(op, a, b) => op.apply(a.eval(env)?, b.eval(env)?),
+        }
+    }
+
+    /// Evaluate modulo `m`, reducing after every step so nothing overflows.
+    /// The result is in `0..m`.
+    ///
+    /// `(a % m) / b` isn't `a / b % m`, so division is an error here.
+    pub fn eval_mod(&self, env: &impl Env, m: i64) -> Result<i64, EvalError> {
+        fn walk(e: &Expr, env: &impl Env, m: i128) -> Result<i128, EvalError> {
+            let n = match e {
+                Expr::Num(n) => *n as i128,
+                Expr::Var(name) => env
+                    .get(name)
+                    .ok_or_else(|| EvalError::Unbound(name.clone()))?
+                    as i128,
+                Expr::Neg(e) => -walk(e, env, m)?,
+                // both sides are below `m`, so even the product fits
+                Expr::Bin(op, a, b) => match op {
+                    Op::Add => walk(a, env, m)? + walk(b, env, m)?,
+                    Op::Sub => walk(a, env, m)? - walk(b, env, m)?,
+                    Op::Mul => walk(a, env, m)? * walk(b, env, m)?,
+                    Op::Div => return Err(EvalError::ModularDivision),
+                },
+            };
+            Ok(n.rem_euclid(m))
+        }
+
+        if m <= 0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        walk(self, env, m as i128).map(|n| n as i64)
+    }
+
+    /// Evaluate every subexpression that doesn't depend on a variable.
+    ///
+    /// Subexpressions that would fail to evaluate (like a division by zero)
+    /// are left as they are, so the error surfaces in [`Expr::eval`] instead.
+    pub fn fold(self) -> Expr {
+        match self {
+            Expr::Num(_) | Expr::Var(_) => self,
+            Expr::Neg(e) => match e.fold() {
+                Expr::Num(n) if n != i64::MIN => Expr::Num(-n),
+                e => Expr::Neg(Box::new(e)),
+            },
+            Expr::Bin(op, a, b) => {
+                let a = a.fold();
+                let b = b.fold();
+                if let (&Expr::Num(x), &Expr::Num(y)) = (&a, &b) {
+                    if let Ok(n) = op.apply(x, y) {
+                        return Expr::Num(n);
+                    }
+                }
+                Expr::bin(op, a, b)
+            }
+        }
+    }
+
+    /// Replace variables with whole expressions. Variables the closure
+    /// returns `None` for are kept.
+    pub fn substitute(&self, f: &mut impl FnMut(&str) -> Option<Expr>) -> Expr {
+        match self {
+            Expr::Num(n) => Expr::Num(*n),
+            Expr::Var(name) => f(name).unwrap_or_else(|| self.clone()),
+            Expr::Neg(e) => Expr::Neg(Box::new(e.substitute(f))),
+            Expr::Bin(op, a, b) => Expr::bin(*op, a.substitute(f), b.substitute(f)),
+        }
+    }
+
+    pub fn contains(&self, var: &str) -> bool {
+        match self {
+            Expr::Num(_) => false,
+            Expr::Var(name) => name == var,
+            Expr::Neg(e) => e.contains(var),
+            Expr::Bin(_, a, b) => a.contains(var) || b.contains(var),
+        }
+    }
+
+    pub fn variables(&self) -> Vec<&str> {
+        fn walk<'e>(e: &'e Expr, vars: &mut Vec<&'e str>) {
+            match e {
+                Expr::Num(_) => {}
+                Expr::Var(name) => {
+                    if !vars.contains(&name.as_str()) {
+                        vars.push(name);
+                    }
+                }
+                Expr::Neg(e) => walk(e, vars),
+                Expr::Bin(_, a, b) => {
+                    walk(a, vars);
+                    walk(b, vars);
+                }
+            }
+        }
+
+        let mut vars = Vec::new();
+        walk(self, &mut vars);
+        vars
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    // the variable doesn't show up on either side
+    Missing,
+    // both sides depend on the variable, or it shows up twice on one side
+    NotLinear,
+    // some other variable is left over after folding
+    NotConstant(String),
+    // e.g. `x * 2 = 5`
+    NoIntegerSolution,
+    Eval(EvalError),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Missing => write!(f, "variable not found in equation"),
+            SolveError::NotLinear => write!(f, "variable must appear exactly once"),
+            SolveError::NotConstant(name) => write!(f, "unbound variable `{name}`"),
+            SolveError::NoIntegerSolution => write!(f, "no integer solution"),
+            SolveError::Eval(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+impl From<EvalError> for SolveError {
+    fn from(e: EvalError) -> Self {
+        SolveError::Eval(e)
+    }
+}
+
+/// Solve `lhs = rhs` for `var`, which must appear exactly once.
+///
+/// Division is integer division, so `x / 4 = 2` is solved as `x = 8`.
+pub fn solve(lhs: &Expr, rhs: &Expr, var: &str) -> Result<i64, SolveError> {
+    let lhs = lhs.clone().fold();
+    let rhs = rhs.clone().fold();
+
+    let (mut expr, mut target) = match (lhs.contains(var), rhs.contains(var)) {
+        (true, false) => (lhs, constant(&rhs)?),
+        (false, true) => (rhs, constant(&lhs)?),
+        (true, true) => return Err(SolveError::NotLinear),
+        (false, false) => return Err(SolveError::Missing),
+    };
+
+    // peel one operation off the variable's side at a time, applying the
+    // inverse to the target
+    loop {
+        expr = match expr {
+            Expr::Var(_) => return Ok(target),
+            Expr::Num(_) => unreachable!("side contains the variable"),
+            Expr::Neg(e) => {
+                target = target.checked_neg().ok_or(EvalError::Overflow)?;
+                *e
+            }
+            Expr::Bin(op, a, b) => match (a.contains(var), b.contains(var)) {
+                (true, false) => {
+                    let c = constant(&b)?;
+                    target = match op {
+                        Op::Add => Op::Sub.apply(target, c)?,
+                        Op::Sub => Op::Add.apply(target, c)?,
+                        Op::Mul => exact_div(target, c)?,
+                        Op::Div => Op::Mul.apply(target, c)?,
+                    };
+                    *a
+                }
+                (false, true) => {
+                    let c = constant(&a)?;
+                    target = match op {
+                        Op::Add => Op::Sub.apply(target, c)?,
+                        Op::Sub => Op::Sub.apply(c, target)?,
+                        Op::Mul => exact_div(target, c)?,
+                        Op::Div => Op::Div.apply(c, target)?,
+                    };
+                    *b
+                }
+                _ => return Err(SolveError::NotLinear),
+            },
+        }
+    }
+}
+
+fn constant(e: &Expr) -> Result<i64, SolveError> {
+    match e.variables().first() {
+        Some(name) => Err(SolveError::NotConstant(name.to_string())),
+        None => Ok(e.eval(&|_: &str| None)?),
+    }
+}
+
+fn exact_div(a: i64, b: i64) -> Result<i64, SolveError> {
+    if b != 0 && a % b != 0 {
+        return Err(SolveError::NoIntegerSolution);
+    }
+    Ok(Op::Div.apply(a, b)?)
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // only parenthesize where precedence (or left associativity) needs it
+        fn operand(f: &mut fmt::Formatter, e: &Expr, parens: bool) -> fmt::Result {
+            if parens {
+                write!(f, "({e})")
+            } else {
+                write!(f, "{e}")
+            }
+        }
+
+        match self {
+            Expr::Num(n) => write!(f, "{n}"),
+            Expr::Var(name) => write!(f, "{name}"),
+            Expr::Neg(e) => {
+                write!(f, "-")?;
+                operand(f, e, matches!(**e, Expr::Bin(..) | Expr::Neg(_)))
+            }
+            Expr::Bin(op, a, b) => {
+                let prec = op.precedence();
+                let left = matches!(**a, Expr::Bin(o, ..) if o.precedence() < prec);
+                let right = matches!(**b, Expr::Bin(o, ..) if o.precedence() <= prec);
+                operand(f, a, left)?;
+                write!(f, " {} ", op.symbol())?;
+                operand(f, b, right)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token<'s> {
+    Num(i64),
+    Ident(&'s str),
+    Op(Op),
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    // byte offset and the offending character
+    UnexpectedChar(usize, char),
+    // byte offset of the token
+    UnexpectedToken(usize),
+    BadNumber(usize),
+    UnexpectedEnd,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedChar(at, c) => write!(f, "unexpected `{c}` at {at}"),
+            ParseError::UnexpectedToken(at) => write!(f, "unexpected token at {at}"),
+            ParseError::BadNumber(at) => write!(f, "number out of range at {at}"),
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Split an expression into tokens, each paired with its byte offset.
+pub fn tokenize(s: &str) -> Result<Vec<(usize, Token<'_>)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((at, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_ascii_digit() || c.is_alphabetic() || c == '_' => {
+                let mut end = at + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                let word = &s[at..end];
+                if c.is_ascii_digit() {
+                    Token::Num(word.parse().map_err(|_| ParseError::BadNumber(at))?)
+                } else {
+                    Token::Ident(word)
+                }
+            }
+            c => return Err(ParseError::UnexpectedChar(at, c)),
+        };
+        tokens.push((at, token));
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'t, 's> {
+    tokens: &'t [(usize, Token<'s>)],
+    pos: usize,
+}
+
+impl<'s> Parser<'_, 's> {
+    fn peek(&self) -> Option<Token<'s>> {
+        self.tokens.get(self.pos).map(|(_, t)| *t)
+    }
+
+    fn next(&mut self) -> Result<Token<'s>, ParseError> {
+        let token = self.peek().ok_or(ParseError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn error(&self) -> ParseError {
+        match self.tokens.get(self.pos) {
+            Some((at, _)) => ParseError::UnexpectedToken(*at),
+            None => ParseError::UnexpectedEnd,
+        }
+    }
+
+    // precedence climbing: parse operators binding at least as tight as `min`
+    fn expr(&mut self, min: u8) -> Result<Expr, ParseError> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(op)) = self.peek() {
+            if op.precedence() < min {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.expr(op.precedence() + 1)?;
+            lhs = Expr::bin(op, lhs, rhs);
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.peek() == Some(Token::Op(Op::Sub)) {
+            self.pos += 1;
+            return Ok(match self.unary()? {
+                Expr::Num(n) => Expr::Num(-n),
+                e => Expr::Neg(Box::new(e)),
+            });
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        let save = self.pos;
+        match self.next()? {
+            Token::Num(n) => Ok(Expr::Num(n)),
+            Token::Ident(name) => Ok(Expr::var(name)),
+            Token::Open => {
+                let e = self.expr(0)?;
+                match self.next() {
+                    Ok(Token::Close) => Ok(e),
+                    Ok(_) => {
+                        self.pos -= 1;
+                        Err(self.error())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            _ => {
+                self.pos = save;
+                Err(self.error())
+            }
+        }
+    }
+}
+
+pub fn parse(s: &str) -> Result<Expr, ParseError> {
+    let tokens = tokenize(s)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+    };
+    let expr = parser.expr(0)?;
+    if parser.pos != tokens.len() {
+        return Err(parser.error());
+    }
+    Ok(expr)
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_print() {
+        for s in [
+            "old * old",
+            "a - (b - c)",
+            "(a + b) * -c",
+            "-(x / 2) + 3",
+            "a - -5",
+        ] {
+            let e: Expr = s.parse().unwrap();
+            assert_eq!(s, e.to_string());
+            assert_eq!(e, e.to_string().parse().unwrap());
+        }
+        assert_eq!("a + b * c", parse("(a) + ((b * c))").unwrap().to_string());
+        assert_eq!(Err(ParseError::UnexpectedToken(4)), parse("1 + )"));
+        assert_eq!(Err(ParseError::UnexpectedEnd), parse("(1 + 2"));
+        assert_eq!(Err(ParseError::UnexpectedChar(2, '%')), parse("a % b"));
+    }
+
+    #[test]
+    fn fold_and_eval() {
+        let e = parse("(2 + 3) * x - 10 / 2").unwrap().fold();
+        assert_eq!("5 * x - 5", e.to_string());
+        assert_eq!(Ok(45), e.eval(&("x", 10)));
+        assert_eq!(Err(EvalError::Unbound("x".into())), e.eval(&|_: &str| None));
+        assert_eq!(
+            Err(EvalError::DivisionByZero),
+            parse("1 / 0").unwrap().eval(&("x", 0))
+        );
+    }
+
+    #[test]
+    fn eval_modulo() {
+        let cube = parse("old * old * old").unwrap();
+        let old = 9_699_689;
+        assert_eq!(Err(EvalError::Overflow), cube.eval(&("old", old)));
+        assert_eq!(Ok(9_699_689), cube.eval_mod(&("old", old), 9_699_690));
+        assert_eq!(Ok(4), parse("1 - old").unwrap().eval_mod(&("old", 4), 7));
+        assert_eq!(
+            Err(EvalError::ModularDivision),
+            parse("old / 2").unwrap().eval_mod(&("old", 4), 7)
+        );
+    }
+
+    #[test]
+    fn solve_for_variable() {
+        let lhs = parse("(4 + 2 * (humn - 3)) / 4").unwrap();
+        let rhs = parse("32 - 2 * 5").unwrap();
+        assert_eq!(Ok(45), solve(&lhs, &rhs, "humn"));
+        assert_eq!(Ok(7), solve(&parse("10 - x").unwrap(), &Expr::Num(3), "x"));
+        assert_eq!(
+            Err(SolveError::NotLinear),
+            solve(&parse("x * x").unwrap(), &Expr::Num(4), "x")
+        );
+        assert_eq!(
+            Err(SolveError::NoIntegerSolution),
+            solve(&parse("2 * x").unwrap(), &Expr::Num(5), "x")
+        );
+    }
+}
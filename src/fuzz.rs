@@ -0,0 +1,281 @@
+//! Search by fuzzing: keep a corpus of genomes, pick one (weighted by score),
+//! mutate it, and keep the mutant if it did something not seen before.
+//!
+//! This never proves anything is optimal, but it finds good answers to
+//! search problems with very little code, so it makes a handy fallback while
+//! working out an exact solution.
+
+use crate::stats;
+use rand::prelude::*;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A problem to fuzz.
+pub trait Target: Sync {
+    type Genome: Clone + Send;
+
+    /// Randomly change a genome, a little.
+    fn mutate(&self, genome: &mut Self::Genome, rng: &mut StdRng);
+
+    /// Higher is better.
+    fn score(&self, genome: &Self::Genome) -> u64;
+
+    /// Feedback on what a genome did besides its score, e.g. a hash of the
+    /// path it took. Mutants join the corpus when their (score, coverage)
+    /// pair is new, so without this only new scores are kept.
+    fn coverage(&self, _genome: &Self::Genome) -> u64 {
+        0
+    }
+}
+
+/// When to stop, and how to split the work. At least one of the budgets
+/// has to be set.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub seed: u64,
+    pub workers: usize,
+    /// Total mutants tried across all workers.
+    pub max_iters: Option<u64>,
+    pub time_limit: Option<Duration>,
+    /// Stop as soon as something scores at least this.
+    pub target_score: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            workers: 1,
+            max_iters: None,
+            time_limit: None,
+            target_score: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome<G> {
+    pub best: G,
+    pub score: u64,
+    pub iterations: u64,
+    pub corpus_size: usize,
+}
+
+/// Where a new best came from, enough to find it again: the same seed and
+/// worker count replay the same run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Found {
+    pub seed: u64,
+    pub worker: usize,
+    /// How many mutants had been tried, across all workers. The seeds are
+    /// iteration 0.
+    pub iteration: u64,
+}
+
+impl fmt::Display for Found {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Found {
+            seed,
+            worker,
+            iteration,
+        } = self;
+        write!(f, "seed {seed}, worker {worker}, iteration {iteration}")
+    }
+}
+
+struct Shared<G> {
+    // genomes and their scores
+    corpus: Vec<(G, u64)>,
+    seen: BTreeSet<(u64, u64)>,
+    best: (G, u64),
+}
+
+// derive an independent seed for each worker thread (splitmix64 finalizer)
+fn worker_seed(seed: u64, worker: usize) -> u64 {
+    let mut z = seed.wrapping_add((worker as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Fuzz `target` starting from `seeds`, calling `on_best` with each new best
+/// genome, its score and where it was found. All workers share one corpus.
+///
+/// With a single worker a given `config.seed` always finds the same answer.
+pub fn fuzz<T: Target>(
+    target: &T,
+    seeds: Vec<T::Genome>,
+    config: &Config,
+    on_best: impl Fn(&T::Genome, u64, Found) + Sync,
+) -> Outcome<T::Genome> {
+    assert!(
+        config.max_iters.is_some() || config.time_limit.is_some() || config.target_score.is_some(),
+        "fuzzing needs a budget"
+    );
+
+    let mut corpus = Vec::new();
+    let mut seen = BTreeSet::new();
+    for genome in seeds {
+        let score = target.score(&genome);
+        seen.insert((score, target.coverage(&genome)));
+        corpus.push((genome, score));
+    }
+    let best = corpus
+        .iter()
+        .max_by_key(|(_, score)| *score)
+        .cloned()
+        .expect("fuzzing needs at least one seed");
+    let found = |worker, iteration| Found {
+        seed: config.seed,
+        worker,
+        iteration,
+    };
+    on_best(&best.0, best.1, found(0, 0));
+
+    let done = AtomicBool::new(config.target_score.is_some_and(|t| best.1 >= t));
+    let shared = Mutex::new(Shared { corpus, seen, best });
+    let iterations = AtomicU64::new(0);
+    let start = Instant::now();
+
+    let worker = |worker: usize| {
+        let mut rng = StdRng::seed_from_u64(worker_seed(config.seed, worker));
+        while !done.load(Ordering::Relaxed) {
+            let iter = iterations.fetch_add(1, Ordering::Relaxed);
+            let out_of_time = config.time_limit.is_some_and(|t| start.elapsed() >= t);
+            if config.max_iters.is_some_and(|m| iter >= m) || out_of_time {
+                done.store(true, Ordering::Relaxed);
+                break;
+            }
+
+            let mut genome = {
+                let shared = shared.lock().unwrap();
+                // +1 so a corpus of all zero scores can still be picked from
+                let (genome, _) = shared
+                    .corpus
+                    .choose_weighted(&mut rng, |item| item.1 + 1)
+                    .unwrap();
+                genome.clone()
+            };
+            target.mutate(&mut genome, &mut rng);
+            let score = target.score(&genome);
+            let coverage = target.coverage(&genome);
+
+            let mut shared = shared.lock().unwrap();
+            if shared.seen.insert((score, coverage)) {
+                if score > shared.best.1 {
+                    shared.best = (genome.clone(), score);
+                    on_best(&genome, score, found(worker, iter + 1));
+                    if config.target_score.is_some_and(|t| score >= t) {
+                        done.store(true, Ordering::Relaxed);
+                    }
+                }
+                shared.corpus.push((genome, score));
+            }
+        }
+    };
+
+    std::thread::scope(|s| {
+        for w in 0..config.workers.max(1) {
+            s.spawn(move || worker(w));
+        }
+    });
+
+    let shared = shared.into_inner().unwrap();
+    // every worker counts the iteration it stopped on too
+    let iterations = iterations.into_inner();
+    let iterations = config.max_iters.map_or(iterations, |m| iterations.min(m));
+    stats::count("fuzz iterations", iterations);
+    Outcome {
+        best: shared.best.0,
+        score: shared.best.1,
+        iterations,
+        corpus_size: shared.corpus.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // count the set bits, with coverage on where the first one is
+    struct OneMax;
+
+    impl Target for OneMax {
+        type Genome = u64;
+
+        fn mutate(&self, genome: &mut u64, rng: &mut StdRng) {
+            *genome ^= 1 << rng.gen_range(0..64);
+        }
+
+        fn score(&self, genome: &u64) -> u64 {
+            genome.count_ones() as u64
+        }
+
+        fn coverage(&self, genome: &u64) -> u64 {
+            genome.trailing_zeros() as u64
+        }
+    }
+
+    #[test]
+    fn one_max() {
+        let config = Config {
+            seed: 7,
+            max_iters: Some(100_000),
+            target_score: Some(64),
+            ..Config::default()
+        };
+        let bests = Mutex::new(Vec::new());
+        let outcome = fuzz(&OneMax, vec![0], &config, |_, score, found| {
+            bests.lock().unwrap().push((score, found))
+        });
+        assert_eq!(u64::MAX, outcome.best);
+        assert_eq!(64, outcome.score);
+        assert!(outcome.iterations < 100_000);
+
+        // scores only ever improve, and the same seed finds the same path
+        let bests = bests.into_inner().unwrap();
+        assert!(bests.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(
+            (
+                0,
+                Found {
+                    seed: 7,
+                    worker: 0,
+                    iteration: 0
+                }
+            ),
+            bests[0]
+        );
+        // the last one is what stopped it
+        assert_eq!(outcome.iterations, bests.last().unwrap().1.iteration);
+        assert!(bests
+            .windows(2)
+            .all(|w| w[0].1.iteration < w[1].1.iteration));
+        assert_eq!(
+            outcome.iterations,
+            fuzz(&OneMax, vec![0], &config, |_, _, _| ()).iterations
+        );
+    }
+
+    #[test]
+    fn budgets() {
+        let config = Config {
+            workers: 4,
+            max_iters: Some(1000),
+            ..Config::default()
+        };
+        let outcome = fuzz(&OneMax, vec![0], &config, |_, _, _| ());
+        assert_eq!(1000, outcome.iterations);
+        assert!(outcome.corpus_size <= 1001);
+
+        let config = Config {
+            workers: 2,
+            time_limit: Some(Duration::from_millis(20)),
+            ..Config::default()
+        };
+        fuzz(&OneMax, vec![0], &config, |_, _, _| ());
+    }
+}
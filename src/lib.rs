@@ -5,14 +5,37 @@ mod days {
 }
 pub use days::*;
 
-pub fn solve_all() -> anyhow::Result<()> {
+pub mod expr;
+pub mod fuzz;
+pub mod params;
+pub mod render;
+pub mod stats;
+pub mod viz;
+
+/// Solve every day, then print what each one recorded in [`stats`] if
+/// `show_stats` is set.
+pub fn solve_all(show_stats: bool) -> anyhow::Result<()> {
+    if show_stats && !stats::ENABLED {
+        anyhow::bail!("--stats needs the `stats` feature");
+    }
     let aoc = emergence::AoC::new(2022)?;
 
+    let mut reports = Vec::new();
     for (day, solver) in SOLVERS.iter().enumerate() {
         let day = day + 1;
         let input = aoc.read_or_fetch(day)?;
-        let solution = solver(&input);
+        let solution = {
+            let _span = stats::span("solve");
+            solver(&input)
+        };
         println!("day {day} solution: {solution}");
+        reports.push((day, stats::take()));
+    }
+
+    if show_stats {
+        for (day, report) in reports {
+            println!("\nday {day} stats:\n{report}");
+        }
     }
 
     Ok(())
@@ -36,10 +59,14 @@ const SOLVERS: &[fn(&str) -> String] = &[
     day15::solve,
     day16::solve,
     day17::solve,
-    fake_solve, // 18
-    fake_solve, // 19
-    fake_solve, // 20
+    day18::solve,
+    day19::solve,
+    day20::solve,
     day21::solve,
+    day22::solve,
+    day23::solve,
+    day24::solve,
+    day25::solve,
 ];
 
 const STATIC_INPUT_SOLVERS: &[(fn(&str) -> String, &str)] = &[
@@ -60,18 +87,72 @@ const STATIC_INPUT_SOLVERS: &[(fn(&str) -> String, &str)] = &[
     (day15::solve, include_str!("inputs/day15.txt")),
     (day16::solve, include_str!("inputs/day16.txt")),
     (day17::solve, include_str!("inputs/day17.txt")),
-    (fake_solve, "fake input"), // 18
-    (fake_solve, "fake input"), // 19
-    (fake_solve, "fake input"), // 20
+    (day18::solve, include_str!("inputs/day18.txt")),
+    (day19::solve, include_str!("inputs/day19.txt")),
+    (day20::solve, include_str!("inputs/day20.txt")),
     (day21::solve, include_str!("inputs/day21.txt")),
 ];
 
-pub fn solve_with_static_input(day: usize) {
-    let (solver, input) = STATIC_INPUT_SOLVERS[day - 1];
+/// Solve a day on its bundled input. Days 22 on aren't bundled, see
+/// [`solve_with_cached_input`].
+pub fn solve_with_static_input(day: usize) -> anyhow::Result<()> {
+    let (solver, input) = STATIC_INPUT_SOLVERS
+        .get(day.wrapping_sub(1))
+        .ok_or_else(|| anyhow::anyhow!("no bundled input for day {day}"))?;
     let solution = solver(input);
     println!("day {day} solution: {solution}");
+    Ok(())
 }
 
-fn fake_solve(_: &str) -> String {
-    "todo".into()
+/// Solve a day whose input isn't bundled, reading it from the same cache
+/// `solve_all` fills (`~/.aoc/2022/dayNN.txt`).
+pub fn solve_with_cached_input(day: usize) -> anyhow::Result<()> {
+    let solver = SOLVERS
+        .get(day.wrapping_sub(1))
+        .ok_or_else(|| anyhow::anyhow!("no such day: {day}"))?;
+    let home = std::env::var_os("HOME").ok_or_else(|| anyhow::anyhow!("$HOME isn't set"))?;
+    let path = std::path::Path::new(&home)
+        .join(".aoc/2022")
+        .join(format!("day{day:02}.txt"));
+    let input = std::fs::read_to_string(&path)
+        .map_err(|e| anyhow::anyhow!("couldn't read input from {}: {e}", path.display()))?;
+
+    let solution = solver(&input);
+    println!("day {day} solution: {solution}");
+    Ok(())
+}
+
+/// Play back one of the simulation days on its bundled input.
+pub fn visualize(day: usize, observer: &mut impl viz::Observer) -> anyhow::Result<()> {
+    let input = match STATIC_INPUT_SOLVERS.get(day.wrapping_sub(1)) {
+        Some((_, input)) => input,
+        None => anyhow::bail!("no such day: {day}"),
+    };
+
+    match day {
+        9 => day09::visualize(input, observer),
+        12 => day12::visualize(input, observer),
+        14 => day14::visualize(input, observer),
+        17 => day17::visualize(input, observer),
+        _ => anyhow::bail!("day {day} has no visualization"),
+    }
+
+    Ok(())
+}
+
+/// Draw the final state of one of the grid days on its bundled input.
+pub fn render(day: usize) -> anyhow::Result<render::Image> {
+    let input = match STATIC_INPUT_SOLVERS.get(day.wrapping_sub(1)) {
+        Some((_, input)) => input,
+        None => anyhow::bail!("no such day: {day}"),
+    };
+
+    Ok(match day {
+        8 => day08::render(input),
+        12 => day12::render(input),
+        14 => day14::render(input),
+        15 => day15::render(input),
+        17 => day17::render(input),
+        _ => anyhow::bail!("day {day} has no image"),
+    })
 }
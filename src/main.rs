@@ -1,3 +1,92 @@
+use advent::viz;
+use anyhow::{bail, Context};
+
+const USAGE: &str = "usage:
+    advent [-p key=value]... [--stats]
+    advent viz <day> [--fps <n>] [--log <file>] [--no-play]
+    advent render <day> --out <file.ppm|file.png>";
+
 fn main() -> anyhow::Result<()> {
-    advent::solve_all()
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().map(String::as_str) == Some("viz") {
+        args.next();
+        return visualize(args);
+    }
+    if args.peek().map(String::as_str) == Some("render") {
+        args.next();
+        return render(args);
+    }
+
+    let mut stats = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--stats" => stats = true,
+            "-p" | "--param" => {
+                let param = args.next().context("-p needs a key=value argument")?;
+                let (key, value) = param
+                    .split_once('=')
+                    .with_context(|| format!("expected key=value, got {param:?}"))?;
+                advent::params::set(key, value);
+            }
+            _ => bail!("unknown argument {arg:?}\n{USAGE}"),
+        }
+    }
+
+    advent::solve_all(stats)
+}
+
+fn visualize(mut args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    let day: usize = args
+        .next()
+        .with_context(|| format!("missing day\n{USAGE}"))?
+        .parse()
+        .context("day should be a number")?;
+
+    let mut fps = 30;
+    let mut log = None;
+    let mut play = true;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fps" => {
+                fps = args
+                    .next()
+                    .context("--fps needs a number")?
+                    .parse()
+                    .context("--fps needs a number")?
+            }
+            "--log" => {
+                let path = args.next().context("--log needs a file name")?;
+                let file = std::fs::File::create(&path)
+                    .with_context(|| format!("couldn't create {path}"))?;
+                log = Some(viz::TextLog::new(std::io::BufWriter::new(file)));
+            }
+            "--no-play" => play = false,
+            _ => bail!("unknown argument {arg:?}\n{USAGE}"),
+        }
+    }
+
+    let terminal = play.then(|| viz::Terminal::new(fps));
+    advent::visualize(day, &mut (terminal, log))
+}
+
+fn render(mut args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    let day: usize = args
+        .next()
+        .with_context(|| format!("missing day\n{USAGE}"))?
+        .parse()
+        .context("day should be a number")?;
+
+    let mut out = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" | "-o" => out = Some(args.next().context("--out needs a file name")?),
+            _ => bail!("unknown argument {arg:?}\n{USAGE}"),
+        }
+    }
+    let out = out.with_context(|| format!("missing --out\n{USAGE}"))?;
+
+    let image = advent::render(day)?;
+    image
+        .save(&out)
+        .with_context(|| format!("couldn't write {out}"))
 }
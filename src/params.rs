@@ -0,0 +1,30 @@
+//! Solver parameters that can be overridden at runtime, e.g. from the command
+//! line with `-p key=value`.
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+static PARAMS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+pub fn set(key: impl Into<String>, value: impl Into<String>) {
+    PARAMS.lock().unwrap().insert(key.into(), value.into());
+}
+
+/// Look up a parameter, or `None` if it was never set.
+///
+/// Panics if the value doesn't parse, since that's a typo by whoever set it.
+pub fn get<T>(key: &str) -> Option<T>
+where
+    T: FromStr,
+    T::Err: Debug,
+{
+    let params = PARAMS.lock().unwrap();
+    let value = params.get(key)?;
+    Some(
+        value
+            .parse()
+            .unwrap_or_else(|e| panic!("bad value {value:?} for parameter {key}: {e:?}")),
+    )
+}
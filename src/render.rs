@@ -0,0 +1,207 @@
+//! Export puzzle state as images: binary PPM always, PNG with the `png`
+//! feature.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+    pub const RED: Rgb = Rgb(220, 40, 40);
+    pub const GREEN: Rgb = Rgb(40, 200, 60);
+    pub const BLUE: Rgb = Rgb(40, 80, 220);
+    pub const YELLOW: Rgb = Rgb(240, 200, 40);
+    pub const GRAY: Rgb = Rgb(110, 110, 110);
+    pub const NAVY: Rgb = Rgb(10, 15, 40);
+
+    /// Linear blend, `t == 0.0` is `self` and `t == 1.0` is `other`.
+    pub fn mix(self, other: Rgb, t: f64) -> Rgb {
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Rgb(
+            lerp(self.0, other.0),
+            lerp(self.1, other.1),
+            lerp(self.2, other.2),
+        )
+    }
+}
+
+/// Heat map colour for `value` within `min..=max`: black, red, yellow, white.
+pub fn heat(value: f64, min: f64, max: f64) -> Rgb {
+    const STOPS: [Rgb; 4] = [Rgb::BLACK, Rgb(200, 30, 10), Rgb(250, 210, 30), Rgb::WHITE];
+
+    let t = if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let scaled = t * (STOPS.len() - 1) as f64;
+    let i = (scaled as usize).min(STOPS.len() - 2);
+    STOPS[i].mix(STOPS[i + 1], scaled - i as f64)
+}
+
+/// A rectangle of values, row-major with (0, 0) at the top left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+pub type Image = Grid<Rgb>;
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Build a grid from rows, which must all be the same length.
+    pub fn from_rows(rows: impl IntoIterator<Item = Vec<T>>) -> Self {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+        for row in rows {
+            assert_eq!(
+                *width.get_or_insert(row.len()),
+                row.len(),
+                "ragged grid rows"
+            );
+            cells.extend(row);
+            height += 1;
+        }
+        Self {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        (x < self.width && y < self.height).then(|| &self.cells[x + y * self.width])
+    }
+
+    /// Set a cell, ignoring anything outside the grid.
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        if x < self.width && y < self.height {
+            self.cells[x + y * self.width] = value;
+        }
+    }
+
+    /// Turn each cell into a colour.
+    pub fn render(&self, color_map: impl Fn(&T) -> Rgb) -> Image {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(color_map).collect(),
+        }
+    }
+}
+
+impl Image {
+    /// Blow each pixel up into a `factor` by `factor` square.
+    pub fn scaled(&self, factor: usize) -> Image {
+        let mut out = Image::new(self.width * factor, self.height * factor, Rgb::BLACK);
+        for y in 0..out.height {
+            for x in 0..out.width {
+                out.cells[x + y * out.width] = self.cells[x / factor + y / factor * self.width];
+            }
+        }
+        out
+    }
+
+    /// Put `other` to the right of this image, `gap` pixels apart.
+    pub fn beside(&self, other: &Image, gap: usize) -> Image {
+        let width = self.width + gap + other.width;
+        let mut out = Image::new(width, self.height.max(other.height), Rgb::BLACK);
+        for (dx, img) in [(0, self), (self.width + gap, other)] {
+            for y in 0..img.height {
+                for x in 0..img.width {
+                    out.cells[dx + x + y * width] = img.cells[x + y * img.width];
+                }
+            }
+        }
+        out
+    }
+
+    pub fn write_ppm(&self, mut out: impl Write) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        let bytes: Vec<u8> = self.cells.iter().flat_map(|c| [c.0, c.1, c.2]).collect();
+        out.write_all(&bytes)
+    }
+
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, out: impl Write) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let bytes: Vec<u8> = self.cells.iter().flat_map(|c| [c.0, c.1, c.2]).collect();
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&bytes).map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+
+    /// Save as PPM, or PNG when the path ends in `.png`.
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let png = path.extension().is_some_and(|e| e == "png");
+        if png && cfg!(not(feature = "png")) {
+            anyhow::bail!("PNG output needs the `png` feature");
+        }
+
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+        #[cfg(feature = "png")]
+        if png {
+            self.write_png(&mut file)?;
+        }
+        if !png {
+            self.write_ppm(&mut file)?;
+        }
+        // dropping the writer flushes it too, but throws away any error
+        file.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ppm() {
+        let grid = Grid::from_rows([vec![0, 1], vec![2, 3]]);
+        let image = grid.render(|&v| heat(v as f64, 0.0, 3.0)).scaled(2);
+        assert_eq!((4, 4), (image.width(), image.height()));
+        assert_eq!(Some(&Rgb::WHITE), image.get(3, 3));
+        assert_eq!(Some(&Rgb::BLACK), image.get(1, 1));
+
+        let mut out = Vec::new();
+        image.beside(&image, 1).write_ppm(&mut out).unwrap();
+        assert!(out.starts_with(b"P6\n9 4\n255\n"));
+        assert_eq!(11 + 9 * 4 * 3, out.len());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn save_reports_write_errors() {
+        // small enough to sit in the buffer until it's flushed
+        let image = Grid::from_rows([vec![0]]).render(|_| Rgb::BLACK);
+        assert!(image.save("/dev/full").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! Named counters and timed spans, for seeing where a solver spends its time.
+//!
+//! Recording only happens with the `stats` feature. Without it [`count`] and
+//! [`span`] compile to nothing and [`take`] always returns an empty report.
+//!
+//! The counters are shared between threads behind a lock, so tight loops
+//! should tally locally and [`count`] the total once.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Whether stats are being recorded at all.
+pub const ENABLED: bool = cfg!(feature = "stats");
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SpanStats {
+    pub calls: u64,
+    pub total: Duration,
+}
+
+/// Everything recorded since the last [`take`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Report {
+    counters: BTreeMap<&'static str, u64>,
+    spans: BTreeMap<&'static str, SpanStats>,
+}
+
+impl Report {
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty() && self.spans.is_empty()
+    }
+
+    pub fn counter(&self, name: &str) -> Option<u64> {
+        self.counters.get(name).copied()
+    }
+
+    pub fn span(&self, name: &str) -> Option<SpanStats> {
+        self.spans.get(name).copied()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  {:<28} {:>14} {:>12}", "name", "count", "time")?;
+        for (name, count) in &self.counters {
+            writeln!(f, "  {name:<28} {count:>14}")?;
+        }
+        for (name, span) in &self.spans {
+            let time = format!("{:.2?}", span.total);
+            writeln!(f, "  {name:<28} {:>14} {time:>12}", span.calls)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "stats")]
+mod imp {
+    use super::Report;
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    static REPORT: Mutex<Report> = Mutex::new(Report {
+        counters: BTreeMap::new(),
+        spans: BTreeMap::new(),
+    });
+
+    pub fn count(name: &'static str, n: u64) {
+        *REPORT.lock().unwrap().counters.entry(name).or_default() += n;
+    }
+
+    /// Times from [`span`] until it's dropped.
+    #[must_use = "the span ends when it's dropped"]
+    pub struct Span {
+        name: &'static str,
+        start: Instant,
+    }
+
+    pub fn span(name: &'static str) -> Span {
+        Span {
+            name,
+            start: Instant::now(),
+        }
+    }
+
+    impl Drop for Span {
+        fn drop(&mut self) {
+            let elapsed = self.start.elapsed();
+            let mut report = REPORT.lock().unwrap();
+            let span = report.spans.entry(self.name).or_default();
+            span.calls += 1;
+            span.total += elapsed;
+        }
+    }
+
+    pub fn take() -> Report {
+        std::mem::take(&mut REPORT.lock().unwrap())
+    }
+}
+
+#[cfg(not(feature = "stats"))]
+mod imp {
+    use super::Report;
+
+    #[inline(always)]
+    pub fn count(_: &'static str, _: u64) {}
+
+    /// Times from [`span`] until it's dropped.
+    #[must_use = "the span ends when it's dropped"]
+    pub struct Span;
+
+    #[inline(always)]
+    pub fn span(_: &'static str) -> Span {
+        Span
+    }
+
+    pub fn take() -> Report {
+        Report::default()
+    }
+}
+
+/// Add `n` to the counter `name`.
+pub use imp::count;
+/// Start timing `name`, until the returned guard is dropped.
+pub use imp::span;
+/// Take everything recorded so far, leaving the stats empty.
+pub use imp::take;
+pub use imp::Span;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record() {
+        // other tests record too, so only look at our own names
+        {
+            let _span = span("stats test span");
+            count("stats test", 2);
+            count("stats test", 3);
+        }
+        let report = take();
+        if ENABLED {
+            assert_eq!(Some(5), report.counter("stats test"));
+            assert_eq!(1, report.span("stats test span").unwrap().calls);
+        } else {
+            assert!(report.is_empty());
+        }
+    }
+}
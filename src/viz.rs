@@ -0,0 +1,280 @@
+//! Frames of intermediate simulation state, played back in the terminal or
+//! dumped to a text log.
+//!
+//! Solvers take an `&mut impl Observer` and only build frames when
+//! [`Observer::ENABLED`] is set, so passing [`Disabled`] compiles the hook
+//! away entirely.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    #[default]
+    Default,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Gray,
+}
+
+impl Color {
+    fn ansi(self) -> &'static str {
+        match self {
+            Color::Default => "\x1b[0m",
+            Color::Red => "\x1b[31m",
+            Color::Green => "\x1b[32m",
+            Color::Yellow => "\x1b[33m",
+            Color::Blue => "\x1b[34m",
+            Color::Magenta => "\x1b[35m",
+            Color::Cyan => "\x1b[36m",
+            Color::White => "\x1b[97m",
+            Color::Gray => "\x1b[90m",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub color: Color,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            color: Color::Default,
+        }
+    }
+}
+
+/// A rectangle of coloured characters, with (0, 0) at the top left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    caption: String,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::default(); width * height],
+            caption: String::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Draw a character, ignoring anything outside the frame.
+    pub fn set(&mut self, x: usize, y: usize, ch: char, color: Color) {
+        if x < self.width && y < self.height {
+            self.cells[x + y * self.width] = Cell { ch, color };
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Cell> {
+        (x < self.width && y < self.height).then(|| self.cells[x + y * self.width])
+    }
+
+    pub fn set_caption(&mut self, caption: impl Into<String>) {
+        self.caption = caption.into();
+    }
+
+    pub fn caption(&self) -> &str {
+        &self.caption
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        // chunks panics on a 0 width
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// The frame with ANSI colour escapes, ready for a terminal.
+    pub fn to_ansi(&self) -> String {
+        let mut out = String::new();
+        for row in self.rows() {
+            let mut color = Color::Default;
+            for cell in row {
+                if cell.color != color {
+                    color = cell.color;
+                    out.push_str(color.ansi());
+                }
+                out.push(cell.ch);
+            }
+            if color != Color::Default {
+                out.push_str(Color::Default.ansi());
+            }
+            out.push('\n');
+        }
+        if !self.caption.is_empty() {
+            let _ = writeln!(out, "{}", self.caption);
+        }
+        out
+    }
+
+    /// The frame as plain text, without a caption.
+    pub fn to_plain(&self) -> String {
+        let mut out = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            out.extend(row.iter().map(|c| c.ch));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+pub trait Observer {
+    /// When this is false solvers skip building frames at all.
+    const ENABLED: bool = true;
+
+    fn frame(&mut self, frame: Frame);
+}
+
+/// The observer for normal solving: frames are never built.
+pub struct Disabled;
+
+impl Observer for Disabled {
+    const ENABLED: bool = false;
+
+    fn frame(&mut self, _: Frame) {}
+}
+
+impl<O: Observer> Observer for &mut O {
+    const ENABLED: bool = O::ENABLED;
+
+    fn frame(&mut self, frame: Frame) {
+        (**self).frame(frame)
+    }
+}
+
+// keep every frame in memory
+impl Observer for Vec<Frame> {
+    fn frame(&mut self, frame: Frame) {
+        self.push(frame);
+    }
+}
+
+// an observer that's only there sometimes
+impl<O: Observer> Observer for Option<O> {
+    const ENABLED: bool = O::ENABLED;
+
+    fn frame(&mut self, frame: Frame) {
+        if let Some(o) = self {
+            o.frame(frame);
+        }
+    }
+}
+
+// send every frame to both observers
+impl<A: Observer, B: Observer> Observer for (A, B) {
+    const ENABLED: bool = A::ENABLED || B::ENABLED;
+
+    fn frame(&mut self, frame: Frame) {
+        if A::ENABLED && B::ENABLED {
+            self.0.frame(frame.clone());
+            self.1.frame(frame);
+        } else if A::ENABLED {
+            self.0.frame(frame);
+        } else {
+            self.1.frame(frame);
+        }
+    }
+}
+
+/// Plays frames in the terminal, at most `fps` frames per second.
+pub struct Terminal {
+    delay: Duration,
+    last: Option<Instant>,
+}
+
+impl Terminal {
+    pub fn new(fps: u32) -> Self {
+        Self {
+            delay: Duration::from_secs(1) / fps.max(1),
+            last: None,
+        }
+    }
+}
+
+impl Observer for Terminal {
+    fn frame(&mut self, frame: Frame) {
+        if let Some(last) = self.last {
+            if let Some(wait) = self.delay.checked_sub(last.elapsed()) {
+                std::thread::sleep(wait);
+            }
+        }
+        self.last = Some(Instant::now());
+
+        // move home and clear, then draw
+        let mut stdout = io::stdout().lock();
+        let _ = write!(stdout, "\x1b[H\x1b[2J{}", frame.to_ansi());
+        let _ = stdout.flush();
+    }
+}
+
+/// Appends every frame to a plain text log, each under a numbered header.
+pub struct TextLog<W: Write> {
+    out: W,
+    frames: usize,
+}
+
+impl<W: Write> TextLog<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, frames: 0 }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> Observer for TextLog<W> {
+    fn frame(&mut self, frame: Frame) {
+        // an observer has nowhere to report errors, so a broken log just
+        // stops growing
+        let _ = writeln!(self.out, "--- frame {}: {}", self.frames, frame.caption());
+        let _ = self.out.write_all(frame.to_plain().as_bytes());
+        self.frames += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames() {
+        let mut frame = Frame::new(3, 2);
+        frame.set(0, 0, '#', Color::Red);
+        frame.set(2, 1, 'o', Color::Default);
+        frame.set(5, 5, 'x', Color::Blue);
+        frame.set_caption("step 1");
+        assert_eq!("#  \n  o\n", frame.to_plain());
+        assert_eq!("\x1b[31m#\x1b[0m  \n  o\nstep 1\n", frame.to_ansi());
+
+        let mut log = (Disabled, TextLog::new(Vec::new()));
+        log.frame(frame.clone());
+        log.frame(frame);
+        let text = String::from_utf8(log.1.into_inner()).unwrap();
+        assert_eq!(
+            "--- frame 0: step 1\n#  \n  o\n--- frame 1: step 1\n#  \n  o\n",
+            text
+        );
+    }
+}